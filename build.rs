@@ -24,11 +24,15 @@
 
 use std::{env, fs::File, io::Write, path::Path};
 
-/// What file to generate the sobol numbers from.
-const DIRECTION_NUMBERS_TEXT: &str = include_str!("direction_numbers/new-joe-kuo-6.1024.txt");
+#[path = "src/direction_numbers.rs"]
+mod direction_numbers;
 
-/// How many components to generate.
-const NUM_DIMENSIONS: usize = 128;
+use direction_numbers::{dimension_count, generate_direction_vectors, SOBOL_BITS};
+
+/// What file to generate the sobol numbers from, unless overridden with
+/// the `SOBOL_DIRECTION_NUMBERS` environment variable.  The other files in
+/// `direction_numbers` can also be loaded at runtime with `SobolTable`.
+const DEFAULT_DIRECTION_NUMBERS_FILE: &str = "direction_numbers/new-joe-kuo-6.1024.txt";
 
 fn main() {
     let out_dir = env::var("OUT_DIR").unwrap();
@@ -37,10 +41,29 @@ fn main() {
     let mut f = File::create(&dest_path).unwrap();
 
     // Init direction vectors.
-    let vectors = generate_direction_vectors(NUM_DIMENSIONS);
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/direction_numbers.rs");
+    println!("cargo:rerun-if-env-changed=SOBOL_DIRECTION_NUMBERS");
+    println!("cargo:rerun-if-env-changed=SOBOL_DIMENSIONS");
+    let direction_numbers_file = env::var("SOBOL_DIRECTION_NUMBERS")
+        .unwrap_or_else(|_| DEFAULT_DIRECTION_NUMBERS_FILE.to_string());
+    println!("cargo:rerun-if-changed={}", direction_numbers_file);
+    let direction_numbers_text = std::fs::read_to_string(&direction_numbers_file)
+        .unwrap_or_else(|e| panic!("Couldn't read \"{}\": {}", direction_numbers_file, e));
+
+    // How many components to generate.  All of the ones in the file, unless
+    // limited with the `SOBOL_DIMENSIONS` environment variable.
+    let num_dimensions = env::var("SOBOL_DIMENSIONS")
+        .map(|n| {
+            n.parse::<usize>()
+                .unwrap_or_else(|_| panic!("Invalid SOBOL_DIMENSIONS \"{}\".", n))
+        })
+        .unwrap_or_else(|_| dimension_count(&direction_numbers_text));
+    let vectors = generate_direction_vectors(&direction_numbers_text, num_dimensions)
+        .unwrap_or_else(|e| panic!("\"{}\": {}", direction_numbers_file, e));
 
     // Write dimensions limit.
-    f.write_all(format!("pub const MAX_DIMENSION: u32 = {};\n", NUM_DIMENSIONS).as_bytes())
+    f.write_all(format!("pub const MAX_DIMENSION: u32 = {};\n", num_dimensions).as_bytes())
         .unwrap();
 
     // Write the vectors.
@@ -56,139 +79,3 @@ fn main() {
     }
     f.write_all("];\n".as_bytes()).unwrap();
 }
-
-//======================================================================
-// The following is adapted from the code on this webpage:
-//
-// http://web.maths.unsw.edu.au/~fkuo/sobol/
-//
-// From these papers:
-//
-//     * S. Joe and F. Y. Kuo, Remark on Algorithm 659: Implementing Sobol's
-//       quasirandom sequence generator, ACM Trans. Math. Softw. 29,
-//       49-57 (2003)
-//
-//     * S. Joe and F. Y. Kuo, Constructing Sobol sequences with better
-//       two-dimensional projections, SIAM J. Sci. Comput. 30, 2635-2654 (2008)
-//
-// The adapted code is under the following license:
-//
-//     Copyright (c) 2008, Frances Y. Kuo and Stephen Joe
-//     All rights reserved.
-//
-//     Redistribution and use in source and binary forms, with or without
-//     modification, are permitted provided that the following conditions are
-//     met:
-//
-//       * Redistributions of source code must retain the above copyright
-//         notice, this list of conditions and the following disclaimer.
-//
-//       * Redistributions in binary form must reproduce the above copyright
-//         notice, this list of conditions and the following disclaimer in the
-//         documentation and/or other materials provided with the
-//         distribution.
-//
-//       * Neither the names of the copyright holders nor the names of the
-//         University of New South Wales and the University of Waikato
-//         and its contributors may be used to endorse or promote products
-//         derived from this software without specific prior written
-//         permission.
-//
-//     THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS ``AS IS'' AND ANY
-//     EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
-//     IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR
-//     PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS BE
-//     LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
-//     CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
-//     SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR
-//     BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
-//     WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE
-//     OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN
-//     IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
-
-type SobolInt = u32;
-const SOBOL_BITS: usize = std::mem::size_of::<SobolInt>() * 8;
-
-pub fn generate_direction_vectors(dimensions: usize) -> Vec<[SobolInt; SOBOL_BITS]> {
-    let mut vectors = Vec::new();
-
-    // Calculate first dimension, which is just the van der Corput sequence.
-    let mut dim_0 = [0 as SobolInt; SOBOL_BITS];
-    for i in 0..SOBOL_BITS {
-        dim_0[i] = 1 << (SOBOL_BITS - 1 - i);
-    }
-    vectors.push(dim_0);
-
-    // Do the rest of the dimensions.
-    let mut lines = DIRECTION_NUMBERS_TEXT.lines();
-    for _ in 1..dimensions {
-        let mut v = [0 as SobolInt; SOBOL_BITS];
-
-        // Get data from the next valid line from the direction numbers text
-        // file.
-        let (s, a, m) = loop {
-            if let Ok((a, m)) = parse_direction_numbers(
-                lines
-                    .next()
-                    .expect("Not enough direction numbers for the requested number of dimensions."),
-            ) {
-                break (m.len(), a, m);
-            }
-        };
-
-        // Generate the direction numbers for this dimension.
-        if SOBOL_BITS <= s as usize {
-            for i in 0..SOBOL_BITS {
-                v[i] = (m[i] << (SOBOL_BITS - 1 - i)) as SobolInt;
-            }
-        } else {
-            for i in 0..(s as usize) {
-                v[i] = (m[i] << (SOBOL_BITS - 1 - i)) as SobolInt;
-            }
-
-            for i in (s as usize)..SOBOL_BITS {
-                v[i] = v[i - s as usize] ^ (v[i - s as usize] >> s);
-
-                for k in 1..s {
-                    v[i] ^= ((a >> (s - 1 - k)) & 1) as SobolInt * v[i - k as usize];
-                }
-            }
-        }
-
-        vectors.push(v);
-    }
-
-    vectors
-}
-
-#[derive(Debug, Copy, Clone)]
-struct ParseError(());
-impl std::error::Error for ParseError {}
-impl std::fmt::Display for ParseError {
-    fn fmt(&self, _f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        Ok(())
-    }
-}
-
-/// Parses the direction numbers from a single line of the direction numbers
-/// text file.  Returns the `a` and `m` parts.
-fn parse_direction_numbers(text: &str) -> Result<(u32, Vec<u32>), Box<dyn std::error::Error>> {
-    let mut numbers = text.split_whitespace();
-    if numbers.clone().count() < 4 || text.starts_with("#") {
-        return Err(Box::new(ParseError(())));
-    }
-
-    // Skip the first two numbers, which are just the dimension and the count
-    // of direction numbers for this dimension.
-    let _ = numbers.next().unwrap().parse::<u32>()?;
-    let _ = numbers.next().unwrap().parse::<u32>()?;
-
-    let a = numbers.next().unwrap().parse::<u32>()?;
-
-    let mut m = Vec::new();
-    for n in numbers {
-        m.push(n.parse::<u32>()?);
-    }
-
-    Ok((a, m))
-}
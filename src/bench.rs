@@ -0,0 +1,266 @@
+//! Timings of the fast paths for evaluating hashes and the Sobol sequence,
+//! checked against the straightforward ways of computing the same thing.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use rand::Rng;
+
+use crate::compiled_hash::{BatchHash, CompiledHash};
+use crate::hash_gen::{exec_hash_slice, exec_hash_slice_batch, HashOp};
+use crate::{rng, sobol};
+
+/// How many inputs `measure_stats()` runs a 32-bit hash on at a time: 64
+/// rounds of an input and its 32 single-bit flips.
+pub const BATCH_SIZE: usize = 64 * 33;
+
+/// How many batches `measure_stats()` runs with each seed.
+pub const BATCHES_PER_SEED: usize = 64;
+
+/// The time one way of running a hash took.
+#[derive(Debug, Copy, Clone)]
+pub struct Timing {
+    pub name: &'static str,
+
+    /// In nanoseconds per hash.
+    pub ns: f64,
+
+    /// Whether the hashes came out the same as with the first way timed
+    /// in the same group.
+    pub matches: bool,
+}
+
+/// The timings from `bench_hash()`.  The first timing of each group is
+/// the baseline that the others are compared with.
+#[derive(Debug, Clone)]
+pub struct HashBench {
+    /// Single blocks of 64 inputs per seed: interpreted, compiled, and
+    /// compiled in a batch.
+    pub blocks: Vec<Timing>,
+
+    /// Batches like `measure_stats()` runs, `BATCH_SIZE` inputs at a time
+    /// and `BATCHES_PER_SEED` batches per seed: element by element, with
+    /// `exec_hash_slice_batch()`, which compiles the hash for every batch,
+    /// and with `BatchHash`, which compiles it once per seed.
+    pub batches: Vec<Timing>,
+}
+
+/// Times about `count` evaluations of a 32-bit hash in each of the ways in
+/// `HashBench`, with inputs and seeds drawn from `seed`.
+pub fn bench_hash(hash_ops: &[HashOp], count: usize, seed: u64) -> HashBench {
+    const BLOCK_SIZE: usize = 64;
+    let blocks = (count / BLOCK_SIZE).max(1);
+    let count = blocks * BLOCK_SIZE;
+
+    let batch_seed_count = (count / (BATCH_SIZE * BATCHES_PER_SEED)).max(1);
+    let batch_count = batch_seed_count * BATCHES_PER_SEED * BATCH_SIZE;
+
+    let mut rng = rng::Pcg32::new(seed);
+    let inputs: Vec<u32> = (0..BLOCK_SIZE).map(|_| rng.gen()).collect();
+    let seeds: Vec<u32> = (0..blocks).map(|_| rng.gen()).collect();
+    let mut buffer = vec![0u32; BLOCK_SIZE];
+    let batch_inputs: Vec<u32> = (0..BATCH_SIZE).map(|_| rng.gen()).collect();
+    let batch_seeds: Vec<u32> = (0..batch_seed_count).map(|_| rng.gen()).collect();
+    let mut batch_buffer = vec![0u32; BATCH_SIZE];
+
+    let mut block_timer = Timer::new(count);
+    block_timer.time("interpreted", &mut || {
+        let mut sum = 0u32;
+        for &seed in seeds.iter() {
+            for &x in inputs.iter() {
+                sum = sum.wrapping_add(exec_hash_slice(black_box(hash_ops), x, seed));
+            }
+        }
+        sum
+    });
+    block_timer.time("compiled", &mut || {
+        let mut sum = 0u32;
+        for &seed in seeds.iter() {
+            let hash = CompiledHash::new(black_box(hash_ops), seed);
+            for &x in inputs.iter() {
+                sum = sum.wrapping_add(hash.exec(x));
+            }
+        }
+        sum
+    });
+    block_timer.time("compiled batch", &mut || {
+        let mut sum = 0u32;
+        for &seed in seeds.iter() {
+            let hash = CompiledHash::new(black_box(hash_ops), seed);
+            buffer.copy_from_slice(&inputs);
+            hash.exec_batch(&mut buffer);
+            for &x in buffer.iter() {
+                sum = sum.wrapping_add(x);
+            }
+        }
+        sum
+    });
+
+    type BatchFn<'a> = dyn Fn(&[u32], u32, &mut [u32]) + 'a; // (inputs, seed, outputs)
+    let mut run_batches = |f: &BatchFn| {
+        let mut sum = 0u32;
+        for &seed in batch_seeds.iter() {
+            for _ in 0..BATCHES_PER_SEED {
+                f(&batch_inputs, seed, &mut batch_buffer);
+                for &x in batch_buffer.iter() {
+                    sum = sum.wrapping_add(x);
+                }
+            }
+        }
+        sum
+    };
+    let mut batch_timer = Timer::new(batch_count);
+    batch_timer.time("per element", &mut || {
+        run_batches(&|inputs, seed, outputs| {
+            for (out, &x) in outputs.iter_mut().zip(inputs.iter()) {
+                *out = exec_hash_slice(black_box(hash_ops), x, seed);
+            }
+        })
+    });
+    batch_timer.time("slice batch", &mut || {
+        run_batches(&|inputs, seed, outputs| {
+            exec_hash_slice_batch(black_box(hash_ops), inputs, seed, outputs)
+        })
+    });
+    batch_timer.time("cached batch", &mut || {
+        let hash = BatchHash::new(black_box(hash_ops));
+        run_batches(&|inputs, seed, outputs| hash.exec(inputs, seed, outputs))
+    });
+
+    HashBench {
+        blocks: block_timer.timings,
+        batches: batch_timer.timings,
+    }
+}
+
+/// The results of `check_sobol()`.  Times are in nanoseconds per
+/// coordinate.
+#[derive(Debug, Copy, Clone)]
+pub struct SobolCheck {
+    pub direct_ns: f64,
+    pub batched_ns: f64,
+    pub gray_code_ns: f64,
+
+    /// How many coordinates from `sobol::fill_u32()` differ from
+    /// `sobol::sobol_u32()`.
+    pub batched_mismatches: usize,
+
+    /// How many coordinates from `sobol::gray_code_iter()` differ from
+    /// `sobol::sobol_u32()` of the index it gives.
+    pub gray_code_mismatches: usize,
+}
+
+impl SobolCheck {
+    pub fn passed(&self) -> bool {
+        self.batched_mismatches == 0 && self.gray_code_mismatches == 0
+    }
+}
+
+/// Checks that `sobol::fill_u32()` and `sobol::gray_code_iter()` give the
+/// same points as `sobol::sobol_u32()`, for `point_count` points starting
+/// at a few different indices in every dimension of the table, and times
+/// all three.
+pub fn check_sobol(point_count: u32) -> SobolCheck {
+    // How many dimensions are checked at a time, to bound the memory used
+    // for the points.
+    const DIMENSION_CHUNK: u32 = 32;
+
+    let dimensions = sobol::table().dimensions();
+    // The last start puts the final point at index `u32::MAX`.
+    let starts = [0, 1, 12345, u32::MAX - (point_count.max(1) - 1)];
+
+    let mut direct_time = 0.0;
+    let mut fill_time = 0.0;
+    let mut gray_code_time = 0.0;
+    let mut fill_mismatches = 0usize;
+    let mut gray_code_mismatches = 0usize;
+    for &start in starts.iter() {
+        for chunk_start in (0..dimensions).step_by(DIMENSION_CHUNK as usize) {
+            let chunk = chunk_start..(chunk_start + DIMENSION_CHUNK).min(dimensions);
+            let chunk_len = chunk.len();
+            let indices = (0..point_count).map(|k| start + k);
+
+            // Direct, one point at a time.
+            let time = Instant::now();
+            let mut direct = vec![0u32; point_count as usize * chunk_len];
+            for (i, point) in indices.zip(direct.chunks_exact_mut(chunk_len)) {
+                for (d, x) in chunk.clone().zip(point.iter_mut()) {
+                    *x = sobol::sobol_u32(i, d);
+                }
+            }
+            black_box(&direct);
+            direct_time += time.elapsed().as_secs_f64();
+
+            // Batched.
+            let time = Instant::now();
+            let mut filled = vec![0u32; direct.len()];
+            sobol::fill_u32(start, chunk.clone(), &mut filled);
+            black_box(&filled);
+            fill_time += time.elapsed().as_secs_f64();
+            fill_mismatches += direct
+                .iter()
+                .zip(filled.iter())
+                .filter(|(a, b)| a != b)
+                .count();
+
+            // Gray-code order, which is checked against the direct
+            // evaluation of the index it gives.
+            let time = Instant::now();
+            let gray_code: Vec<Vec<(u32, u32)>> = chunk
+                .clone()
+                .map(|d| {
+                    sobol::gray_code_iter(d, start)
+                        .take(point_count as usize)
+                        .collect()
+                })
+                .collect();
+            black_box(&gray_code);
+            gray_code_time += time.elapsed().as_secs_f64();
+            gray_code_mismatches += chunk
+                .clone()
+                .zip(gray_code.iter())
+                .flat_map(|(d, points)| points.iter().map(move |&p| (d, p)))
+                .filter(|&(d, (i, x))| x != sobol::sobol_u32(i, d))
+                .count();
+        }
+    }
+
+    let total = (starts.len() as u64 * point_count as u64 * dimensions as u64) as f64;
+    SobolCheck {
+        direct_ns: direct_time * 1.0e9 / total,
+        batched_ns: fill_time * 1.0e9 / total,
+        gray_code_ns: gray_code_time * 1.0e9 / total,
+        batched_mismatches: fill_mismatches,
+        gray_code_mismatches,
+    }
+}
+
+/// Times a group of benchmarks of the same `count` evaluations, checking
+/// their results against the first one's.
+struct Timer {
+    count: usize,
+    reference: Option<u32>,
+    timings: Vec<Timing>,
+}
+
+impl Timer {
+    fn new(count: usize) -> Timer {
+        Timer {
+            count,
+            reference: None,
+            timings: Vec::new(),
+        }
+    }
+
+    fn time(&mut self, name: &'static str, f: &mut dyn FnMut() -> u32) {
+        let start = Instant::now();
+        let result = f();
+        let seconds = start.elapsed().as_secs_f64();
+        let reference = *self.reference.get_or_insert(result);
+        self.timings.push(Timing {
+            name,
+            ns: seconds * 1.0e9 / self.count as f64,
+            matches: result == reference,
+        });
+    }
+}
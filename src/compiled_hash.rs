@@ -0,0 +1,273 @@
+//! Faster evaluation of `HashOp` hashes, by specializing them for a
+//! given seed.
+//!
+//! `exec_hash_slice()` interprets the ops, checking for each op whether
+//! it uses the seed.  But for a fixed seed all of that can be resolved up
+//! front: the seed-derived constants are computed once, `ShlAdd` and
+//! `SeedMix` are turned into plain multiplies and adds, and runs of
+//! the same kind of op are fused together.  The result can then be run on
+//! single values, or on whole arrays of values one op at a time, which
+//! the compiler can vectorize.
+
+use std::sync::Mutex;
+
+use crate::hash_gen::HashOp;
+use crate::word::Word;
+
+/// A single operation of a compiled hash.  Unlike `HashOp`, constants
+/// are always used as-is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CompiledOp<W = u32> {
+    Xor(W),      // x ^= constant
+    Add(W),      // x += constant
+    Mul(W),      // x *= constant
+    ShlXor(u32), // x ^= x << constant
+    MulXor(W),   // x ^= x * constant
+}
+
+/// A hash specialized for a single seed.
+#[derive(Debug, Clone)]
+pub struct CompiledHash<W = u32> {
+    ops: Vec<CompiledOp<W>>,
+}
+
+impl<W: Word> CompiledHash<W> {
+    /// Specializes `hash_ops` for `seed`.  The result computes exactly
+    /// the same thing as `exec_hash_slice(hash_ops, x, seed)`.
+    pub fn new(hash_ops: &[HashOp<W>], seed: W) -> CompiledHash<W> {
+        let mut hash = CompiledHash {
+            ops: Vec::with_capacity(hash_ops.len() + 1),
+        };
+
+        // Resolves the "zero means seed" convention.
+        let c_or = |c: W, seed_value: W| if c == W::ZERO { seed_value } else { c };
+        let seed_shift = seed & W::from_u64(W::BITS as u64 - 1);
+
+        for op in hash_ops.iter() {
+            match *op {
+                HashOp::Nop => {}
+                HashOp::Xor(c) => hash.push(CompiledOp::Xor(c_or(c, seed))),
+                HashOp::Add(c) => hash.push(CompiledOp::Add(c_or(c, seed))),
+                HashOp::Mul(c) => hash.push(CompiledOp::Mul(c_or(c, seed | W::ONE))),
+                HashOp::ShlXor(c) => {
+                    let shift = c_or(c, seed_shift).to_u64() as u32;
+                    hash.push(CompiledOp::ShlXor(shift));
+                }
+                HashOp::ShlAdd(c) => {
+                    // x + (x << c) == x * (1 + (1 << c))
+                    let shift = c_or(c, seed_shift).to_u64() as u32;
+                    hash.push(CompiledOp::Mul(W::ONE.wrapping_add(W::ONE << shift)));
+                }
+                HashOp::MulXor(c) => hash.push(CompiledOp::MulXor(c_or(c, seed & !W::ONE))),
+                HashOp::SeedMix => {
+                    hash.push(CompiledOp::Add(seed));
+                    hash.push(CompiledOp::Mul((seed >> (W::BITS / 2)) | W::ONE));
+                }
+            }
+        }
+
+        hash
+    }
+
+    pub fn ops(&self) -> &[CompiledOp<W>] {
+        &self.ops
+    }
+
+    /// Runs the hash on a single value.
+    #[inline]
+    pub fn exec(&self, x: W) -> W {
+        let mut x = x;
+        for op in self.ops.iter() {
+            x = match *op {
+                CompiledOp::Xor(c) => x ^ c,
+                CompiledOp::Add(c) => x.wrapping_add(c),
+                CompiledOp::Mul(c) => x.wrapping_mul(c),
+                CompiledOp::ShlXor(c) => x ^ (x << c),
+                CompiledOp::MulXor(c) => x ^ x.wrapping_mul(c),
+            };
+        }
+        x
+    }
+
+    /// Runs the hash in-place on all of `xs`.
+    ///
+    /// This is done one op at a time over the whole slice, so that each
+    /// inner loop is trivially vectorizable.
+    pub fn exec_batch(&self, xs: &mut [W]) {
+        for op in self.ops.iter() {
+            match *op {
+                CompiledOp::Xor(c) => xs.iter_mut().for_each(|x| *x = *x ^ c),
+                CompiledOp::Add(c) => xs.iter_mut().for_each(|x| *x = x.wrapping_add(c)),
+                CompiledOp::Mul(c) => xs.iter_mut().for_each(|x| *x = x.wrapping_mul(c)),
+                CompiledOp::ShlXor(c) => xs.iter_mut().for_each(|x| *x = *x ^ (*x << c)),
+                CompiledOp::MulXor(c) => xs.iter_mut().for_each(|x| *x = *x ^ x.wrapping_mul(c)),
+            }
+        }
+    }
+
+    /// Appends an op, fusing it with the previous op when possible and
+    /// dropping it if it does nothing.
+    fn push(&mut self, op: CompiledOp<W>) {
+        let fused = match (self.ops.last().copied(), op) {
+            (Some(CompiledOp::Xor(a)), CompiledOp::Xor(b)) => Some(CompiledOp::Xor(a ^ b)),
+            (Some(CompiledOp::Add(a)), CompiledOp::Add(b)) => {
+                Some(CompiledOp::Add(a.wrapping_add(b)))
+            }
+            (Some(CompiledOp::Mul(a)), CompiledOp::Mul(b)) => {
+                Some(CompiledOp::Mul(a.wrapping_mul(b)))
+            }
+            _ => None,
+        };
+        let op = match fused {
+            Some(fused) => {
+                self.ops.pop();
+                fused
+            }
+            None => op,
+        };
+
+        let is_identity = match op {
+            CompiledOp::Xor(c) | CompiledOp::Add(c) | CompiledOp::MulXor(c) => c == W::ZERO,
+            CompiledOp::Mul(c) => c == W::ONE,
+            CompiledOp::ShlXor(_) => false,
+        };
+        if !is_identity {
+            self.ops.push(op);
+        }
+    }
+}
+
+/// A `HashOp` hash for running on batches of inputs, which keeps the hash
+/// compiled for the seed it was last run with.
+///
+/// `exec_hash_slice_batch()` compiles the hash on every call, which adds
+/// up when it's called over and over with the same seed, like
+/// `measure_stats()` does.  The compiled hash is cached per rayon thread,
+/// so a `BatchHash` can be shared between threads.
+pub struct BatchHash<W = u32> {
+    hash_ops: Vec<HashOp<W>>,
+    cache: Vec<CacheSlot<W>>,
+}
+
+/// A thread's hash compiled for the last seed it ran with, if any.
+type CacheSlot<W> = Mutex<Option<(W, CompiledHash<W>)>>;
+
+impl<W: Word> BatchHash<W> {
+    pub fn new(hash_ops: &[HashOp<W>]) -> BatchHash<W> {
+        // One slot for each thread of the current rayon pool, plus one for
+        // threads outside of it.
+        BatchHash {
+            hash_ops: hash_ops.to_vec(),
+            cache: (0..=rayon::current_num_threads())
+                .map(|_| Mutex::new(None))
+                .collect(),
+        }
+    }
+
+    /// Same as `exec_hash_slice_batch()`.
+    pub fn exec(&self, inputs: &[W], seed: W, outputs: &mut [W]) {
+        // For tiny batches compiling the hash isn't worth it.
+        if inputs.len() < 8 {
+            for (out, &x) in outputs.iter_mut().zip(inputs.iter()) {
+                *out = crate::hash_gen::exec_hash_slice(&self.hash_ops, x, seed);
+            }
+            return;
+        }
+
+        outputs.copy_from_slice(inputs);
+        let slot = rayon::current_thread_index().map_or(0, |i| i + 1);
+        match self.cache.get(slot) {
+            Some(cached) => {
+                let mut cached = cached.lock().unwrap();
+                match *cached {
+                    Some((cached_seed, ref hash)) if cached_seed == seed => {}
+                    _ => *cached = Some((seed, CompiledHash::new(&self.hash_ops, seed))),
+                }
+                cached.as_ref().unwrap().1.exec_batch(outputs);
+            }
+            // A thread of some other rayon pool.
+            None => CompiledHash::new(&self.hash_ops, seed).exec_batch(outputs),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_gen::{exec_hash_slice, exec_hash_slice_batch};
+    use crate::rng::Pcg32;
+
+    /// The same op with its constant set to 0, so that it uses the seed.
+    fn seeded<W: Word>(op: HashOp<W>) -> HashOp<W> {
+        match op {
+            HashOp::Xor(_) => HashOp::Xor(W::ZERO),
+            HashOp::Add(_) => HashOp::Add(W::ZERO),
+            HashOp::Mul(_) => HashOp::Mul(W::ZERO),
+            HashOp::ShlXor(_) => HashOp::ShlXor(W::ZERO),
+            HashOp::ShlAdd(_) => HashOp::ShlAdd(W::ZERO),
+            HashOp::MulXor(_) => HashOp::MulXor(W::ZERO),
+            HashOp::Nop | HashOp::SeedMix => op,
+        }
+    }
+
+    /// Checks every way of running random hashes against
+    /// `exec_hash_slice()`, with batches both smaller and larger than the
+    /// size where compiling kicks in.
+    fn check_against_interpreter<W: Word>() {
+        let mut rng = Pcg32::new(3);
+        for program in 0..200 {
+            let len = 1 + program % 12;
+            let hash_ops: Vec<HashOp<W>> = (0..len)
+                .map(|i| {
+                    let op = HashOp::gen_random(&mut rng);
+                    // Every other program uses the seed much more than
+                    // `gen_random()` would on its own.
+                    if program % 2 == 1 && i % 2 == 0 {
+                        seeded(op)
+                    } else {
+                        op
+                    }
+                })
+                .collect();
+            let batch_hash = BatchHash::new(&hash_ops);
+
+            for &batch_size in [1usize, 7, 8, 9, 64].iter() {
+                let seed = W::random(&mut rng);
+                let inputs: Vec<W> = (0..batch_size).map(|_| W::random(&mut rng)).collect();
+                let expected: Vec<W> = inputs
+                    .iter()
+                    .map(|&x| exec_hash_slice(&hash_ops, x, seed))
+                    .collect();
+
+                let compiled = CompiledHash::new(&hash_ops, seed);
+                let single: Vec<W> = inputs.iter().map(|&x| compiled.exec(x)).collect();
+                assert_eq!(single, expected, "{:?}", hash_ops);
+
+                let mut batch = inputs.clone();
+                compiled.exec_batch(&mut batch);
+                assert_eq!(batch, expected, "{:?}", hash_ops);
+
+                let mut outputs = vec![W::ZERO; batch_size];
+                exec_hash_slice_batch(&hash_ops, &inputs, seed, &mut outputs);
+                assert_eq!(outputs, expected, "{:?}", hash_ops);
+
+                // Twice, to also run with the hash cached for this seed.
+                for _ in 0..2 {
+                    let mut outputs = vec![W::ZERO; batch_size];
+                    batch_hash.exec(&inputs, seed, &mut outputs);
+                    assert_eq!(outputs, expected, "{:?}", hash_ops);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn compiled_hashes_match_the_interpreter_u32() {
+        check_against_interpreter::<u32>();
+    }
+
+    #[test]
+    fn compiled_hashes_match_the_interpreter_u64() {
+        check_against_interpreter::<u64>();
+    }
+}
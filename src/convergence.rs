@@ -0,0 +1,274 @@
+//! Integration-error convergence tests.
+//!
+//! Integrates a suite of analytic test functions with a sampler over many
+//! seeds, and measures how the RMS error falls off with the number of
+//! points, like the convergence plots in Burley's "Practical Hash-based
+//! Owen Scrambling".  For reference, plain Monte Carlo converges with a
+//! slope of -0.5 on a log-log plot, and Owen-scrambled Sobol points get
+//! close to -1.5 for smooth functions and -0.75 for discontinuous ones in
+//! 2D.
+
+use rayon::prelude::*;
+
+/// A test function on the unit hypercube, with a known integral.
+pub struct TestFunction {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub dimensions: u32,
+    pub f: fn(&[f64]) -> f64,
+    pub integral: fn() -> f64, // The exact integral over [0, 1)^dimensions.
+}
+
+/// All of the test functions.
+pub const TEST_FUNCTIONS: &[TestFunction] = &[
+    TestFunction {
+        name: "gaussian",
+        description: "Smooth 2D Gaussian centered on the unit square.",
+        dimensions: 2,
+        f: gaussian,
+        integral: gaussian_integral,
+    },
+    TestFunction {
+        name: "heaviside",
+        description: "2D step across a slanted line.",
+        dimensions: 2,
+        f: heaviside,
+        integral: || 0.5,
+    },
+    TestFunction {
+        name: "disk",
+        description: "2D disk of radius 0.4.",
+        dimensions: 2,
+        f: disk,
+        integral: || std::f64::consts::PI * DISK_RADIUS * DISK_RADIUS,
+    },
+    TestFunction {
+        name: "bilinear",
+        description: "x * y.",
+        dimensions: 2,
+        f: bilinear,
+        integral: || 0.25,
+    },
+    TestFunction {
+        name: "g_function",
+        description: "8D product function (Sobol's g-function) with a_k = k.",
+        dimensions: 8,
+        f: g_function,
+        integral: || 1.0,
+    },
+];
+
+/// Computes the RMS integration error of `function` with `sample`, for
+/// each power-of-two point count from 1 to `2^max_log2_points`.
+///
+/// The error for each seed is computed from a single run of
+/// `2^max_log2_points` points, using the prefix of the run for the smaller
+/// point counts.  Dimension `d` of the function is dimension `d` of the
+/// sampler, and `seed` is passed as-is for every dimension.
+pub fn rmse_by_point_count<F>(
+    function: &TestFunction,
+    sample: F,
+    max_log2_points: u32,
+    seeds: &[u32],
+) -> Vec<f64>
+where
+    F: Fn(u32, u32, u32) -> f32 + Sync, // (sample_index, dimension, seed) -> coordinate
+{
+    let integral = (function.integral)();
+    let squared_errors: Vec<f64> = seeds
+        .par_iter()
+        .map(|&seed| {
+            let mut errors = vec![0.0f64; max_log2_points as usize + 1];
+            let mut point = vec![0.0f64; function.dimensions as usize];
+            let mut sum = 0.0;
+            for i in 0..(1u32 << max_log2_points) {
+                for (d, x) in point.iter_mut().enumerate() {
+                    *x = sample(i, d as u32, seed) as f64;
+                }
+                sum += (function.f)(&point);
+
+                let n = i + 1;
+                if n.is_power_of_two() {
+                    let error = sum / n as f64 - integral;
+                    errors[n.trailing_zeros() as usize] = error * error;
+                }
+            }
+            errors
+        })
+        .reduce(
+            || vec![0.0f64; max_log2_points as usize + 1],
+            |mut a, b| {
+                for (a, b) in a.iter_mut().zip(b.iter()) {
+                    *a += b;
+                }
+                a
+            },
+        );
+
+    squared_errors
+        .iter()
+        .map(|e| (e / seeds.len() as f64).sqrt())
+        .collect()
+}
+
+/// How the integration error of one test function falls off with the
+/// point count.  See `convergence()`.
+#[derive(Debug, Clone)]
+pub struct Convergence {
+    /// Indexed by log2(N), as returned by `rmse_by_point_count()`.
+    pub rmse: Vec<f64>,
+
+    /// The slope from `convergence_slope()`, fitted to the upper half of
+    /// the point counts, where the asymptotic behavior dominates.  `None`
+    /// if too few of those errors are non-zero to fit a line.
+    pub slope: Option<f64>,
+}
+
+/// Computes the RMS integration error of `function` with `sample` with
+/// `rmse_by_point_count()`, along with its convergence slope.
+pub fn convergence<F>(
+    function: &TestFunction,
+    sample: F,
+    max_log2_points: u32,
+    seeds: &[u32],
+) -> Convergence
+where
+    F: Fn(u32, u32, u32) -> f32 + Sync, // (sample_index, dimension, seed) -> coordinate
+{
+    let rmse = rmse_by_point_count(function, sample, max_log2_points, seeds);
+    let slope = convergence_slope(&rmse, max_log2_points as usize / 2);
+    Convergence { rmse, slope }
+}
+
+/// Fits a line to log2(RMSE) vs. log2(N) by least squares, for the
+/// point counts from `2^from_log2_points` up, and returns its slope.
+///
+/// `rmse` is indexed by log2(N), as returned by `rmse_by_point_count()`.
+/// Zero errors are skipped, since they have no logarithm.  Returns `None`
+/// if fewer than two errors are left.
+pub fn convergence_slope(rmse: &[f64], from_log2_points: usize) -> Option<f64> {
+    let points: Vec<(f64, f64)> = rmse
+        .iter()
+        .enumerate()
+        .skip(from_log2_points)
+        .filter(|(_, &e)| e > 0.0)
+        .map(|(log2_n, &e)| (log2_n as f64, e.log2()))
+        .collect();
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+    let covariance: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    let variance: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.0 - mean_x)).sum();
+    Some(covariance / variance)
+}
+
+//----------------------------------------------------------------------
+
+const GAUSSIAN_SIGMA: f64 = 0.2;
+const DISK_RADIUS: f64 = 0.4;
+
+fn gaussian_1d(x: f64) -> f64 {
+    let x = x - 0.5;
+    (-(x * x) / (2.0 * GAUSSIAN_SIGMA * GAUSSIAN_SIGMA)).exp()
+}
+
+fn gaussian(p: &[f64]) -> f64 {
+    gaussian_1d(p[0]) * gaussian_1d(p[1])
+}
+
+fn gaussian_integral() -> f64 {
+    // The Gaussian is separable, so the integral is the square of the 1D
+    // integral, which is computed with Simpson's rule.  With this many
+    // intervals that's accurate to far beyond what the tests can resolve.
+    const INTERVALS: usize = 1 << 16;
+    let h = 1.0 / INTERVALS as f64;
+    let mut sum = gaussian_1d(0.0) + gaussian_1d(1.0);
+    for i in 1..INTERVALS {
+        let weight = if i % 2 == 1 { 4.0 } else { 2.0 };
+        sum += weight * gaussian_1d(i as f64 * h);
+    }
+    let integral_1d = sum * h / 3.0;
+    integral_1d * integral_1d
+}
+
+fn heaviside(p: &[f64]) -> f64 {
+    if p[1] < 0.15 + 0.7 * p[0] {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+fn disk(p: &[f64]) -> f64 {
+    let x = p[0] - 0.5;
+    let y = p[1] - 0.5;
+    if x * x + y * y < DISK_RADIUS * DISK_RADIUS {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+fn bilinear(p: &[f64]) -> f64 {
+    p[0] * p[1]
+}
+
+fn g_function(p: &[f64]) -> f64 {
+    p.iter()
+        .enumerate()
+        .map(|(k, &x)| {
+            let a = k as f64 + 1.0;
+            ((4.0 * x - 2.0).abs() + a) / (1.0 + a)
+        })
+        .product()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Integrates `function` with the midpoint rule on a grid of about
+    /// 2^22 cells.
+    fn midpoint_integral(function: &TestFunction) -> f64 {
+        let dims = function.dimensions as usize;
+        // An even number of cells per side, so the cell edges fall on 0.5.
+        let per_side = ((1u64 << 22) as f64).powf(1.0 / dims as f64) as usize & !1;
+        let cells = per_side.pow(dims as u32);
+        let mut point = vec![0.0f64; dims];
+        let mut sum = 0.0;
+        for cell in 0..cells {
+            let mut k = cell;
+            for x in point.iter_mut() {
+                *x = ((k % per_side) as f64 + 0.5) / per_side as f64;
+                k /= per_side;
+            }
+            sum += (function.f)(&point);
+        }
+        sum / cells as f64
+    }
+
+    #[test]
+    fn integrals_match_the_midpoint_rule() {
+        for function in TEST_FUNCTIONS.iter() {
+            let exact = (function.integral)();
+            let estimate = midpoint_integral(function);
+            assert!(
+                (exact - estimate).abs() < 1.0e-4,
+                "{}: {} vs. {}",
+                function.name,
+                exact,
+                estimate
+            );
+        }
+    }
+
+    #[test]
+    fn slope_needs_two_non_zero_errors() {
+        assert_eq!(convergence_slope(&[1.0, 0.5, 0.25], 0), Some(-1.0));
+        assert_eq!(convergence_slope(&[1.0, 0.5, 0.0], 1), None);
+        assert_eq!(convergence_slope(&[], 0), None);
+    }
+}
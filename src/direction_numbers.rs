@@ -0,0 +1,153 @@
+//! Generation of Sobol direction vectors from Joe-Kuo direction numbers.
+//!
+//! This is shared between `build.rs`, which uses it to generate the
+//! built-in table, and `sobol::SobolTable`, which uses it to load tables
+//! at runtime.
+
+//======================================================================
+// The following is adapted from the code on this webpage:
+//
+// http://web.maths.unsw.edu.au/~fkuo/sobol/
+//
+// From these papers:
+//
+//     * S. Joe and F. Y. Kuo, Remark on Algorithm 659: Implementing Sobol's
+//       quasirandom sequence generator, ACM Trans. Math. Softw. 29,
+//       49-57 (2003)
+//
+//     * S. Joe and F. Y. Kuo, Constructing Sobol sequences with better
+//       two-dimensional projections, SIAM J. Sci. Comput. 30, 2635-2654 (2008)
+//
+// The adapted code is under the following license:
+//
+//     Copyright (c) 2008, Frances Y. Kuo and Stephen Joe
+//     All rights reserved.
+//
+//     Redistribution and use in source and binary forms, with or without
+//     modification, are permitted provided that the following conditions are
+//     met:
+//
+//       * Redistributions of source code must retain the above copyright
+//         notice, this list of conditions and the following disclaimer.
+//
+//       * Redistributions in binary form must reproduce the above copyright
+//         notice, this list of conditions and the following disclaimer in the
+//         documentation and/or other materials provided with the
+//         distribution.
+//
+//       * Neither the names of the copyright holders nor the names of the
+//         University of New South Wales and the University of Waikato
+//         and its contributors may be used to endorse or promote products
+//         derived from this software without specific prior written
+//         permission.
+//
+//     THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS ``AS IS'' AND ANY
+//     EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+//     IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR
+//     PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS BE
+//     LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+//     CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+//     SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR
+//     BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//     WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE
+//     OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN
+//     IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+pub type SobolInt = u32;
+pub const SOBOL_BITS: usize = std::mem::size_of::<SobolInt>() * 8;
+
+/// The number of dimensions that a Joe-Kuo direction numbers file has
+/// direction numbers for, including the first dimension, which doesn't
+/// need any.
+pub fn dimension_count(direction_numbers_text: &str) -> usize {
+    1 + direction_numbers_text
+        .lines()
+        .filter(|line| parse_direction_numbers(line).is_ok())
+        .count()
+}
+
+/// Generates the direction vectors for the first `dimensions` dimensions
+/// of the Sobol sequence, from the text of a Joe-Kuo direction numbers
+/// file.
+pub fn generate_direction_vectors(
+    direction_numbers_text: &str,
+    dimensions: usize,
+) -> Result<Vec<[SobolInt; SOBOL_BITS]>, String> {
+    let mut vectors = Vec::new();
+
+    // Calculate first dimension, which is just the van der Corput sequence.
+    let mut dim_0 = [0 as SobolInt; SOBOL_BITS];
+    for (i, v) in dim_0.iter_mut().enumerate() {
+        *v = 1 << (SOBOL_BITS - 1 - i);
+    }
+    vectors.push(dim_0);
+
+    // Do the rest of the dimensions.
+    let mut lines = direction_numbers_text.lines();
+    for _ in 1..dimensions {
+        let mut v = [0 as SobolInt; SOBOL_BITS];
+
+        // Get data from the next valid line from the direction numbers text
+        // file.
+        let (s, a, m) = loop {
+            let line = lines.next().ok_or_else(|| {
+                format!(
+                    "Not enough direction numbers for {} dimensions, only found {}.",
+                    dimensions,
+                    vectors.len()
+                )
+            })?;
+            if let Ok((a, m)) = parse_direction_numbers(line) {
+                break (m.len(), a, m);
+            }
+        };
+
+        // Generate the direction numbers for this dimension.
+        for (i, (v, m)) in v.iter_mut().zip(m.iter()).enumerate() {
+            *v = (m << (SOBOL_BITS - 1 - i)) as SobolInt;
+        }
+        for i in s..SOBOL_BITS {
+            v[i] = v[i - s] ^ (v[i - s] >> s);
+
+            for k in 1..s {
+                v[i] ^= ((a >> (s - 1 - k)) & 1) as SobolInt * v[i - k];
+            }
+        }
+
+        vectors.push(v);
+    }
+
+    Ok(vectors)
+}
+
+#[derive(Debug, Copy, Clone)]
+struct ParseError(());
+impl std::error::Error for ParseError {}
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, _f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+        Ok(())
+    }
+}
+
+/// Parses the direction numbers from a single line of the direction numbers
+/// text file.  Returns the `a` and `m` parts.
+fn parse_direction_numbers(text: &str) -> Result<(u32, Vec<u32>), Box<dyn std::error::Error>> {
+    let mut numbers = text.split_whitespace();
+    if numbers.clone().count() < 4 || text.starts_with("#") {
+        return Err(Box::new(ParseError(())));
+    }
+
+    // Skip the first two numbers, which are just the dimension and the count
+    // of direction numbers for this dimension.
+    let _ = numbers.next().unwrap().parse::<u32>()?;
+    let _ = numbers.next().unwrap().parse::<u32>()?;
+
+    let a = numbers.next().unwrap().parse::<u32>()?;
+
+    let mut m = Vec::new();
+    for n in numbers {
+        m.push(n.parse::<u32>()?);
+    }
+
+    Ok((a, m))
+}
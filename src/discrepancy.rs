@@ -0,0 +1,203 @@
+//! Discrepancy measures of point sets.
+//!
+//! These are the L2 discrepancies from Hickernell's "A generalized
+//! discrepancy and quadrature error bound" (1998), computed with their
+//! closed-form O(N^2 D) expressions.  Lower is better for all of them,
+//! and for all of them a good D-dimensional sequence converges roughly as
+//! O(log(N)^D / N), compared to O(1 / sqrt(N)) for random points.
+
+use rayon::prelude::*;
+
+/// The discrepancies of a point set.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Discrepancies {
+    pub l2_star: f64,
+    pub centered: f64,
+    pub wrap_around: f64,
+    pub symmetric: f64,
+}
+
+impl Discrepancies {
+    pub const NAMES: [&'static str; 4] = ["L2-star", "centered", "wrap-around", "symmetric"];
+
+    pub fn values(&self) -> [f64; 4] {
+        [
+            self.l2_star,
+            self.centered,
+            self.wrap_around,
+            self.symmetric,
+        ]
+    }
+
+    fn map<F: Fn(f64) -> f64>(&self, f: F) -> Discrepancies {
+        Discrepancies {
+            l2_star: f(self.l2_star),
+            centered: f(self.centered),
+            wrap_around: f(self.wrap_around),
+            symmetric: f(self.symmetric),
+        }
+    }
+
+    fn add(&self, other: &Discrepancies) -> Discrepancies {
+        Discrepancies {
+            l2_star: self.l2_star + other.l2_star,
+            centered: self.centered + other.centered,
+            wrap_around: self.wrap_around + other.wrap_around,
+            symmetric: self.symmetric + other.symmetric,
+        }
+    }
+}
+
+/// Computes the discrepancies of the first `n` points of a `dimensions`
+/// dimensional point set, using dimensions `0..dimensions` of `sample`.
+///
+/// `seed` is passed to `sample` as-is for every dimension, so it's up to
+/// the sampler to decorrelate the dimensions if needed.
+pub fn discrepancies<F>(sample: F, n: u32, dimensions: u32, seed: u32) -> Discrepancies
+where
+    F: Fn(u32, u32, u32) -> f32, // (sample_index, dimension, seed) -> coordinate
+{
+    let points: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..dimensions).map(|d| sample(i, d, seed) as f64).collect())
+        .collect();
+
+    discrepancies_squared(&points).map(f64::sqrt)
+}
+
+/// Same as `discrepancies()`, but averaged over many seeds.
+///
+/// The squared discrepancies are averaged, so the result is the root mean
+/// square discrepancy over the seeds.
+pub fn average_discrepancies<F>(sample: F, n: u32, dimensions: u32, seeds: &[u32]) -> Discrepancies
+where
+    F: Fn(u32, u32, u32) -> f32, // (sample_index, dimension, seed) -> coordinate
+{
+    let mut sum = Discrepancies::default();
+    for &seed in seeds.iter() {
+        let d = discrepancies(&sample, n, dimensions, seed);
+        sum = sum.add(&d.map(|v| v * v));
+    }
+    sum.map(|v| (v / seeds.len() as f64).sqrt())
+}
+
+/// Same as `average_discrepancies()`, for each power-of-two point count
+/// from 1 to `2^max_log2_points`.  Item `m` is for `2^m` points.
+pub fn discrepancies_by_point_count<F>(
+    sample: F,
+    max_log2_points: u32,
+    dimensions: u32,
+    seeds: &[u32],
+) -> Vec<Discrepancies>
+where
+    F: Fn(u32, u32, u32) -> f32, // (sample_index, dimension, seed) -> coordinate
+{
+    (0..=max_log2_points)
+        .map(|m| average_discrepancies(&sample, 1 << m, dimensions, seeds))
+        .collect()
+}
+
+/// Computes all of the squared discrepancies of a point set, where each
+/// point is a slice of coordinates in [0, 1).
+fn discrepancies_squared(points: &[Vec<f64>]) -> Discrepancies {
+    let n = points.len() as f64;
+    let dims = points.first().map(|p| p.len()).unwrap_or(0) as i32;
+
+    // The single sums.
+    let mut star_sum = 0.0;
+    let mut centered_sum = 0.0;
+    let mut symmetric_sum = 0.0;
+    for p in points.iter() {
+        let mut star = 1.0;
+        let mut centered = 1.0;
+        let mut symmetric = 1.0;
+        for &x in p.iter() {
+            let c = (x - 0.5).abs();
+            star *= 1.0 - x * x;
+            centered *= 1.0 + 0.5 * c - 0.5 * c * c;
+            symmetric *= 1.0 + 2.0 * x - 2.0 * x * x;
+        }
+        star_sum += star;
+        centered_sum += centered;
+        symmetric_sum += symmetric;
+    }
+
+    // The double sums, over all ordered pairs of points.
+    let pair_sums = points
+        .par_iter()
+        .map(|a| {
+            let mut sums = Discrepancies::default();
+            for b in points.iter() {
+                let mut star = 1.0;
+                let mut centered = 1.0;
+                let mut wrap_around = 1.0;
+                let mut symmetric = 1.0;
+                for (&x, &y) in a.iter().zip(b.iter()) {
+                    let diff = (x - y).abs();
+                    star *= 1.0 - x.max(y);
+                    centered *= 1.0 + 0.5 * (x - 0.5).abs() + 0.5 * (y - 0.5).abs() - 0.5 * diff;
+                    wrap_around *= 1.5 - diff * (1.0 - diff);
+                    symmetric *= 1.0 - diff;
+                }
+                sums.l2_star += star;
+                sums.centered += centered;
+                sums.wrap_around += wrap_around;
+                sums.symmetric += symmetric;
+            }
+            sums
+        })
+        .reduce(Discrepancies::default, |a, b| a.add(&b));
+
+    let n2 = n * n;
+    Discrepancies {
+        l2_star: 3.0f64.powi(-dims) - 2.0f64.powi(1 - dims) / n * star_sum + pair_sums.l2_star / n2,
+        centered: (13.0f64 / 12.0).powi(dims) - 2.0 / n * centered_sum + pair_sums.centered / n2,
+        wrap_around: -(4.0f64 / 3.0).powi(dims) + pair_sums.wrap_around / n2,
+        symmetric: (4.0f64 / 3.0).powi(dims) - 2.0 / n * symmetric_sum
+            + 2.0f64.powi(dims) * pair_sums.symmetric / n2,
+    }
+    // Rounding can make a tiny squared discrepancy slightly negative.
+    .map(|v| v.max(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rng, sobol};
+
+    fn assert_squares(d: Discrepancies, expected: [f64; 4]) {
+        for (name, (&v, &e)) in Discrepancies::NAMES
+            .iter()
+            .zip(d.values().iter().zip(expected.iter()))
+        {
+            assert!((v * v - e).abs() < 1.0e-12, "{}: {} != {}", name, v * v, e);
+        }
+    }
+
+    #[test]
+    fn one_dimensional_sets_match_hand_computed_values() {
+        // A single point at 0.5.
+        let d = discrepancies(|_, _, _| 0.5, 1, 1, 0);
+        assert_squares(d, [1.0 / 12.0, 1.0 / 12.0, 1.0 / 6.0, 1.0 / 3.0]);
+
+        // The points {0, 0.5}.
+        let d = discrepancies(|i, _, _| i as f32 * 0.5, 2, 1, 0);
+        assert_squares(d, [1.0 / 12.0, 1.0 / 12.0, 1.0 / 24.0, 1.0 / 3.0]);
+    }
+
+    #[test]
+    fn sobol_discrepancy_falls_as_points_double() {
+        let seeds = rng::seeds(1, 4);
+        let by_count = discrepancies_by_point_count(sobol::sample_owen, 8, 3, &seeds);
+        assert_eq!(by_count.len(), 9);
+        for pair in by_count.windows(2) {
+            for (i, (&a, &b)) in pair[0]
+                .values()
+                .iter()
+                .zip(pair[1].values().iter())
+                .enumerate()
+            {
+                assert!(b < a, "{}: {:?}", Discrepancies::NAMES[i], by_count);
+            }
+        }
+    }
+}
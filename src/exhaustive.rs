@@ -0,0 +1,247 @@
+//! Exact hash statistics, computed by exhaustively evaluating a reduced
+//! bit-width hash over all of its inputs.
+//!
+//! `measure_stats()` estimates the stats by random sampling, which
+//! converges slowly for the tiny biases expected at the high bits.  For
+//! hashes on small words (see `HashOp::exec_bits()`) it's feasible to
+//! instead evaluate every input for a fixed set of seeds, giving exact
+//! stats to validate the sampling estimator against.
+
+use rayon::prelude::*;
+
+use crate::stats::{measure_stats_estimate, Stats, StatsEstimate};
+
+/// The largest word size `exhaustive_stats()` supports.
+pub const MAX_BITS: u32 = 20;
+
+/// The largest word size for which `exhaustive_stats()` computes the tree
+/// bias.  It's computed over all pairs of inputs, which gets expensive
+/// fast.
+pub const MAX_TREE_BIAS_BITS: u32 = 12;
+
+/// Computes the exact stats of a `bits`-bit hash, averaged over the
+/// given seeds.  The returned stats are `bits`-bit stats.
+///
+/// The stats mean the same thing as the stats from `measure_stats()`, with
+/// all inputs weighted equally, except that the bias for each seed is
+/// exact rather than estimated from a few thousand inputs.  The tree bias
+/// is zero for hashes wider than `MAX_TREE_BIAS_BITS`.
+pub fn exhaustive_stats<F>(hash: F, bits: u32, seeds: &[u32]) -> Stats
+where
+    F: Fn(u32, u32) -> u32 + Sync, // (input, seed) -> output
+{
+    assert!((1..=MAX_BITS).contains(&bits));
+    let bits = bits as usize;
+    let size = 1usize << bits;
+
+    let mut stats = Stats::zero(bits);
+    for &seed in seeds.iter() {
+        let table: Vec<u32> = (0..size as u32)
+            .into_par_iter()
+            .map(|x| hash(x, seed))
+            .collect();
+
+        // Avalanche and avalanche bias.
+        let counts = (0..size)
+            .into_par_iter()
+            .fold(
+                || [[0u64; 32]; 32],
+                |mut counts, x| {
+                    for bit_in in 0..bits {
+                        let diff = table[x] ^ table[x ^ (1 << bit_in)];
+                        for bit_out in 0..bits {
+                            counts[bit_in][bit_out] += ((diff >> bit_out) & 1) as u64;
+                        }
+                    }
+                    counts
+                },
+            )
+            .reduce(|| [[0u64; 32]; 32], add_counts);
+        for bit_in in 0..bits {
+            for bit_out in 0..bits {
+                let p = counts[bit_in][bit_out] as f64 / size as f64;
+                stats.avalanche[bit_in][bit_out] += p;
+                stats.avalanche_avg_bias[bit_in][bit_out] += (p * 2.0 - 1.0).abs();
+            }
+        }
+
+        // Tree seeding bias, over all ordered pairs of inputs.
+        if bits as u32 <= MAX_TREE_BIAS_BITS {
+            let counts = (0..size)
+                .into_par_iter()
+                .fold(
+                    || [[0u64; 32]; 32],
+                    |mut counts, a| {
+                        for b in 0..size {
+                            let mut x = table[a] ^ table[b];
+                            let mut y = (a ^ b) as u32;
+                            while x & 1 == 0 && y & 1 == 0 && (x != 0 || y != 0) {
+                                x >>= 1;
+                                y >>= 1;
+                            }
+                            y = y.reverse_bits() >> 26;
+                            x = x.reverse_bits() >> 26;
+                            counts[x as usize & 0b11111][y as usize & 0b11111] += 1;
+                        }
+                        counts
+                    },
+                )
+                .reduce(|| [[0u64; 32]; 32], add_counts);
+            for i in 0..32 {
+                for j in 0..32 {
+                    stats.tree_bias[i][j] +=
+                        counts[i][j] as f64 / (size * size) as f64 * 0.5 * 32.0 * 32.0;
+                }
+            }
+        }
+    }
+
+    for i in 0..bits {
+        for j in 0..bits {
+            stats.avalanche[i][j] /= seeds.len() as f64;
+            stats.avalanche_avg_bias[i][j] /= seeds.len() as f64;
+        }
+    }
+    for i in 0..32 {
+        for j in 0..32 {
+            stats.tree_bias[i][j] /= seeds.len() as f64;
+        }
+    }
+
+    stats
+}
+
+/// How far sampled stats are from the exact stats.  See
+/// `compare_sampled()`.
+#[derive(Debug, Copy, Clone)]
+pub struct SampledError {
+    /// The largest difference in the avalanche, in standard errors.
+    pub avalanche_max_z: f64,
+
+    /// The mean difference in the average bias, sampled minus exact.
+    pub avg_bias_mean_difference: f64,
+}
+
+/// Measures the stats of a `bits`-bit hash with
+/// `measure_stats_estimate()`, picking the hash seed from the given seeds,
+/// to compare with `exhaustive_stats()` for the same seeds.
+///
+/// They're measured on 32-bit words with the inputs masked to `bits`
+/// bits, so only the lowest `bits` bits of the stats mean anything.
+pub fn sampled_stats_estimate<F>(
+    hash: F,
+    bits: u32,
+    seeds: &[u32],
+    rounds: u32,
+    seed: u64,
+    print_progress: bool,
+) -> StatsEstimate
+where
+    F: Fn(u32, u32) -> u32 + Sync, // (input, seed) -> output
+{
+    let mask = !0u32 >> (32 - bits);
+    measure_stats_estimate(
+        |inputs, s, outputs| {
+            let s = seeds[s as usize % seeds.len()];
+            for (out, &n) in outputs.iter_mut().zip(inputs.iter()) {
+                *out = hash(n & mask, s);
+            }
+        },
+        rounds,
+        seed,
+        print_progress,
+    )
+}
+
+/// Compares the stats from `sampled_stats_estimate()` with the exact
+/// stats: in standard errors for the avalanche, which is a plain average,
+/// and in absolute terms for the bias, whose estimator has a noise floor.
+///
+/// The tree bias isn't compared, because `measure_stats()` draws 32-bit
+/// inputs and the tree bias also looks at the input bits above the
+/// exact stats' `bits`.
+pub fn compare_sampled(exact: &Stats, sampled: &StatsEstimate) -> SampledError {
+    let bits = exact.bits;
+    let mut max_z = 0.0f64;
+    let mut bias_diff = 0.0;
+    for bit_in in 0..bits {
+        for bit_out in (bit_in + 1)..bits {
+            let error = sampled.std_error.avalanche[bit_in][bit_out];
+            if error > 0.0 {
+                let diff =
+                    sampled.stats.avalanche[bit_in][bit_out] - exact.avalanche[bit_in][bit_out];
+                max_z = max_z.max(diff.abs() / error);
+            }
+            bias_diff += sampled.stats.avalanche_avg_bias[bit_in][bit_out]
+                - exact.avalanche_avg_bias[bit_in][bit_out];
+        }
+    }
+    SampledError {
+        avalanche_max_z: max_z,
+        avg_bias_mean_difference: bias_diff / (bits * (bits - 1) / 2).max(1) as f64,
+    }
+}
+
+fn add_counts(mut a: [[u64; 32]; 32], b: [[u64; 32]; 32]) -> [[u64; 32]; 32] {
+    for i in 0..32 {
+        for j in 0..32 {
+            a[i][j] += b[i][j];
+        }
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_gen::exec_hash_slice_bits;
+    use crate::{hashes, rng};
+
+    /// Checks that the avalanche is exactly `expected(bit_in, bit_out)`, and
+    /// so the bias is exactly 1 everywhere.
+    fn assert_avalanche(stats: &Stats, expected: impl Fn(usize, usize) -> bool) {
+        for bit_in in 0..stats.bits {
+            for bit_out in 0..stats.bits {
+                let p = if expected(bit_in, bit_out) { 1.0 } else { 0.0 };
+                assert_eq!(
+                    stats.avalanche[bit_in][bit_out], p,
+                    "{} {}",
+                    bit_in, bit_out
+                );
+                assert_eq!(stats.avalanche_avg_bias[bit_in][bit_out], 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn linear_hashes_have_known_stats() {
+        let seeds = [0, 1, 0x5a, 0xff];
+        let identity = exhaustive_stats(|n, _| n, 8, &seeds);
+        assert_eq!(identity.bits, 8);
+        assert_avalanche(&identity, |i, j| i == j);
+
+        // Xoring in the seed changes nothing.
+        let xor = exhaustive_stats(|n, s| (n ^ s) & 0xff, 8, &seeds);
+        assert_avalanche(&xor, |i, j| i == j);
+
+        // Each input bit flips itself and the bit above it.
+        let shift_xor = exhaustive_stats(|n, _| (n ^ (n << 1)) & 0xff, 8, &seeds);
+        assert_avalanche(&shift_xor, |i, j| j == i || j == i + 1);
+    }
+
+    #[test]
+    fn sampled_stats_match_exact_stats() {
+        let ops = hashes::get::<u32>("fast").unwrap().ops.unwrap();
+        let bits = 6;
+        let seeds: Vec<u32> = rng::seeds(1, 8)
+            .iter()
+            .map(|s| s & ((1 << bits) - 1))
+            .collect();
+        let hash = |n, s| exec_hash_slice_bits(ops, n, s, bits);
+        let exact = exhaustive_stats(hash, bits, &seeds);
+        let sampled = sampled_stats_estimate(hash, bits, &seeds, 1 << 16, 2, false);
+        let error = compare_sampled(&exact, &sampled);
+        assert!(error.avalanche_max_z < 4.0, "{:?}", error);
+        assert!(error.avg_bias_mean_difference.abs() < 0.05, "{:?}", error);
+    }
+}
@@ -0,0 +1,584 @@
+//! The experiments run by the `sample_test` binary, as functions that
+//! return their results instead of printing them, so that other tools can
+//! run them too.
+//!
+//! Each experiment derives all of its random numbers from the seed it's
+//! passed, so it gives the same results for the same seed.
+
+use std::path::Path;
+
+use rand::Rng;
+
+use crate::convergence::{convergence, Convergence, TestFunction, TEST_FUNCTIONS};
+use crate::discrepancy::{discrepancies_by_point_count, Discrepancies};
+use crate::exhaustive::{compare_sampled, exhaustive_stats, sampled_stats_estimate, SampledError};
+use crate::hashes::{HashWord, SelectedHash};
+use crate::independence::{worst_deviations, PairDeviation, MAX_DEVIATION};
+use crate::nets::{
+    independently_shuffled_prefixes_are_nets, prefix_net_failures, scrambled_net_failures,
+    shuffled_prefixes_are_nets, sobol_quality_parameters, NetFailures,
+};
+use crate::samplers::Sampler;
+use crate::search::Search;
+use crate::spectrum::{power_spectrum, Spectrum};
+use crate::stats::{measure_stats_estimate, normal_cdf, score_stats, Stats, StatsEstimate};
+use crate::stats_diff::{diff_stats, StatsDiff};
+use crate::word::Word;
+use crate::{exhaustive, rng, sobol};
+
+/// The largest power-of-two point count, as a log2, of the net checks and
+/// the discrepancy table.
+const MAX_LOG2_POINTS: u32 = 12;
+
+/// Same as `MAX_LOG2_POINTS`, for the convergence table.
+const CONVERGENCE_MAX_LOG2_POINTS: u32 = 14;
+
+/// How many rounds `check_exhaustive()` measures the sampled stats with.
+pub const EXHAUSTIVE_SAMPLED_ROUNDS: u32 = 1 << 22;
+
+/// The point sets that the point set experiments compare, each with the
+/// seeds to average it over: the unscrambled Sobol sequence, which only
+/// needs one, Owen scrambled with `hash` (see `Sampler::owen()`), and the
+/// reference Owen scramble.
+fn compared_samplers<'a, 's>(
+    hash: Option<&'a SelectedHash>,
+    seeds: &'s [u32],
+) -> [(Sampler<'a>, &'s [u32]); 3] {
+    [
+        (Sampler::unscrambled(), &seeds[..1]),
+        (Sampler::owen(hash), seeds),
+        (Sampler::reference(), seeds),
+    ]
+}
+
+//----------------------------------------------------------------------
+// Hash statistics.
+
+/// The stats of a hash.  See `test_hash()`.
+#[derive(Debug, Clone)]
+pub struct HashTest {
+    pub estimate: StatsEstimate,
+    pub score: f64,
+
+    /// The standard error of `score`, if there were enough rounds to
+    /// estimate it.
+    pub score_error: Option<f64>,
+}
+
+/// Measures the stats of a hash with `measure_stats_estimate()`.
+pub fn test_hash<W: HashWord>(
+    hash: &SelectedHash<W>,
+    rounds: u32,
+    seed: u64,
+    print_progress: bool,
+) -> HashTest {
+    let estimate = measure_stats_estimate(hash.batch_hasher(), rounds, seed, print_progress);
+    let score = score_stats(&estimate.stats);
+    let score_error = estimate.std_error_of(score_stats);
+    HashTest {
+        estimate,
+        score,
+        score_error,
+    }
+}
+
+/// The stats of two hashes, and the difference of their scores.  See
+/// `compare_hashes()`.
+#[derive(Debug, Clone)]
+pub struct HashComparison {
+    pub a: HashTest,
+    pub b: HashTest,
+
+    /// The score of B minus the score of A, like in `StatsDiff`, so it's
+    /// negative if B is better.
+    pub difference: f64,
+
+    /// The paired standard error of `difference`, if there were enough
+    /// rounds to estimate it.
+    pub difference_error: Option<f64>,
+}
+
+impl HashComparison {
+    /// The z-score and two-sided p-value of `difference`, if there's an
+    /// error estimate to test it with.
+    ///
+    /// An error of zero, such as when comparing a hash with itself, gives
+    /// z = 0 and p = 1 if there's no difference either, and an infinite z
+    /// with p = 0 otherwise.
+    pub fn significance(&self) -> Option<(f64, f64)> {
+        let error = self.difference_error?;
+        if error == 0.0 {
+            return Some(if self.difference == 0.0 {
+                (0.0, 1.0)
+            } else {
+                (self.difference.signum() * f64::INFINITY, 0.0)
+            });
+        }
+        let z = self.difference / error;
+        Some((z, 2.0 * (1.0 - normal_cdf(z.abs()))))
+    }
+}
+
+/// Measures the stats of two hashes, for testing whether one of them
+/// scores significantly better than the other.
+///
+/// Both hashes are measured with the same seed, so they see the same
+/// inputs and the comparison can be paired.
+pub fn compare_hashes<W: HashWord>(
+    hash_a: &SelectedHash<W>,
+    hash_b: &SelectedHash<W>,
+    rounds: u32,
+    seed: u64,
+    print_progress: bool,
+) -> HashComparison {
+    let a = test_hash(hash_a, rounds, seed, print_progress);
+    let b = test_hash(hash_b, rounds, seed, print_progress);
+    let difference = b.score - a.score;
+    let difference_error =
+        StatsEstimate::std_error_of_difference(&b.estimate, &a.estimate, score_stats);
+    HashComparison {
+        a,
+        b,
+        difference,
+        difference_error,
+    }
+}
+
+/// One side of a diff: a hash to measure, or stats from elsewhere, such
+/// as ones saved with `stats_file::write_stats_file()`.
+pub enum DiffInput<W: HashWord> {
+    Hash(SelectedHash<W>),
+    Stats(Box<Stats>),
+}
+
+/// Two stats and their per-output-bit comparison.  See `diff_hashes()`.
+#[derive(Debug, Clone)]
+pub struct HashDiff {
+    pub stats_a: Stats,
+    pub stats_b: Stats,
+
+    /// Whether both sides were measured, rather than given as stats.
+    /// Only then can the diff have error estimates, and only if there
+    /// were enough rounds, see `StatsDiff::has_std_error`.
+    pub measured: bool,
+
+    pub diff: StatsDiff,
+}
+
+/// Compares the stats of two hashes, or of given stats, output bit by
+/// output bit with `diff_stats()`.
+///
+/// Hashes are measured with the same seed, like in `compare_hashes()`, so
+/// if both sides are hashes the differences are tested for significance.
+/// Returns an error if the stats are for different word sizes.
+pub fn diff_hashes<W: HashWord>(
+    a: &DiffInput<W>,
+    b: &DiffInput<W>,
+    rounds: u32,
+    seed: u64,
+    print_progress: bool,
+) -> Result<HashDiff, String> {
+    let measure = |input: &DiffInput<W>| match input {
+        DiffInput::Hash(hash) => {
+            let estimate =
+                measure_stats_estimate(hash.batch_hasher(), rounds, seed, print_progress);
+            (estimate.stats, Some(estimate))
+        }
+        DiffInput::Stats(stats) => (**stats, None),
+    };
+    let (stats_a, estimate_a) = measure(a);
+    let (stats_b, estimate_b) = measure(b);
+    let estimates = estimate_a.as_ref().zip(estimate_b.as_ref());
+    let diff = diff_stats(&stats_a, &stats_b, estimates)?;
+    Ok(HashDiff {
+        stats_a,
+        stats_b,
+        measured: estimates.is_some(),
+        diff,
+    })
+}
+
+/// The exact stats of a reduced bit width hash, and how the sampled stats
+/// compare to them.  See `check_exhaustive()`.
+#[derive(Debug, Clone)]
+pub struct ExhaustiveCheck {
+    /// The hash seeds the stats are over.
+    pub seeds: Vec<u32>,
+
+    pub exact: Stats,
+
+    /// Measured with `EXHAUSTIVE_SAMPLED_ROUNDS` rounds, on 32-bit words.
+    /// See `exhaustive::sampled_stats_estimate()`.
+    pub sampled: StatsEstimate,
+
+    pub error: SampledError,
+}
+
+/// Computes the exact stats of a `bits`-bit version of a hash over
+/// `seed_count` seeds, and the sampled stats from
+/// `measure_stats_estimate()` for the same hash and seeds to compare them
+/// with.
+///
+/// Panics if `bits` is 0 or more than `exhaustive::MAX_BITS`.
+pub fn check_exhaustive(
+    hash: &SelectedHash,
+    bits: u32,
+    seed_count: usize,
+    seed: u64,
+    print_progress: bool,
+) -> ExhaustiveCheck {
+    assert!(
+        (1..=exhaustive::MAX_BITS).contains(&bits),
+        "exhaustive stats need between 1 and {} bits, not {}",
+        exhaustive::MAX_BITS,
+        bits
+    );
+    let mask = !0u32 >> (32 - bits);
+    let mut rng = rng::Pcg32::new(seed);
+    let seeds: Vec<u32> = (0..seed_count.max(1))
+        .map(|_| rng.gen::<u32>() & mask)
+        .collect();
+
+    let exact = exhaustive_stats(|n, s| hash.exec_bits(n, s, bits), bits, &seeds);
+
+    // Sampled stats, picking the hash seed from the same set of seeds.
+    let sampled = sampled_stats_estimate(
+        |n, s| hash.exec_bits(n, s, bits),
+        bits,
+        &seeds,
+        EXHAUSTIVE_SAMPLED_ROUNDS,
+        rng.gen(),
+        print_progress,
+    );
+    let error = compare_sampled(&exact, &sampled);
+
+    ExhaustiveCheck {
+        seeds,
+        exact,
+        sampled,
+        error,
+    }
+}
+
+/// Runs a search until it's done, calling `progress` before each round.
+///
+/// If `checkpoint` is given, the search state is saved to that file every
+/// that many rounds, and when the search finishes.  Failing to save a
+/// checkpoint doesn't stop the search: the error is passed to
+/// `checkpoint_error`, and the search carries on.
+pub fn run_search<W, P, E>(
+    search: &mut Search<W>,
+    checkpoint: Option<(&Path, usize)>,
+    mut progress: P,
+    mut checkpoint_error: E,
+) where
+    W: Word,
+    P: FnMut(&Search<W>),
+    E: FnMut(std::io::Error),
+{
+    let mut save_checkpoint = |search: &Search<W>| {
+        if let Some((path, _)) = checkpoint {
+            if let Err(e) = search.write_checkpoint(path) {
+                checkpoint_error(e);
+            }
+        }
+    };
+
+    while !search.is_done() {
+        progress(search);
+        search.step();
+
+        if let Some((_, every)) = checkpoint {
+            if search.round % every.max(1) == 0 {
+                save_checkpoint(search);
+            }
+        }
+    }
+    save_checkpoint(search);
+}
+
+//----------------------------------------------------------------------
+// Point sets.
+
+/// The largest deviations from independence of the scrambles of pairs of
+/// dimensions.  See `check_independence()`.
+#[derive(Debug, Clone)]
+pub struct IndependenceCheck {
+    /// With the dimension seeds from `sobol::dimension_seed()`, for each
+    /// of `independence::INDICES`.
+    pub derived: Vec<PairDeviation>,
+
+    /// The control, with the seed shared by all dimensions.
+    pub shared: Vec<PairDeviation>,
+}
+
+impl IndependenceCheck {
+    /// Whether the derived dimension seeds gave independent scrambles.
+    pub fn passed(&self) -> bool {
+        self.derived.iter().all(|d| d.deviation < MAX_DEVIATION)
+    }
+}
+
+/// Checks that `sobol::dimension_seed()` gives independent scrambles for
+/// each pair of the first `dimensions` dimensions, with
+/// `independence::worst_deviations()`.
+///
+/// As a control, the same test is run with the seed shared by all
+/// dimensions, which should fail.
+pub fn check_independence(
+    sampler: &Sampler,
+    dimensions: u32,
+    seed_count: usize,
+    seed: u64,
+) -> IndependenceCheck {
+    let seeds = rng::seeds(seed, seed_count.max(1));
+    let scramble = |i, d, s| sampler.scramble(i, d, s);
+    IndependenceCheck {
+        derived: worst_deviations(scramble, sobol::dimension_seed, dimensions, &seeds),
+        shared: worst_deviations(scramble, |_, s| s, dimensions, &seeds),
+    }
+}
+
+/// Whether Owen scrambled points are still nets.  See `check_nets()`.
+#[derive(Clone)]
+pub struct NetsCheck<'a> {
+    /// The `t` of the unscrambled points, for each power-of-two point
+    /// count.  Item `m` is for `2^m` points.
+    pub t: Vec<u32>,
+
+    /// The Owen scrambled and the reference scrambled points, each with
+    /// its failures for each point count.
+    pub results: Vec<(Sampler<'a>, Vec<NetFailures>)>,
+}
+
+impl<'a> NetsCheck<'a> {
+    /// Whether all of the scrambled points were nets.
+    pub fn passed(&self) -> bool {
+        self.results
+            .iter()
+            .all(|(_, failures)| failures.iter().all(|f| f.first.is_none()))
+    }
+}
+
+/// Checks that Owen scrambling preserves the net properties of the Sobol
+/// sequence in the given dimensions, for `seed_count` seeds, with
+/// `nets::scrambled_net_failures()`.
+///
+/// For each power-of-two point count, the `t` of the unscrambled points is
+/// found, and then the points scrambled as in `sobol::sample_owen_fast()`
+/// (or with `hash` if given) and `sobol::sample_owen_reference()` are
+/// checked against it.  A hash that isn't a proper nested scramble breaks
+/// stratification, which the avalanche stats can't show directly.
+pub fn check_nets<'a>(
+    hash: Option<&'a SelectedHash>,
+    dimensions: &[u32],
+    seed_count: usize,
+    seed: u64,
+) -> NetsCheck<'a> {
+    let seeds = rng::seeds(seed, seed_count.max(1));
+    let t = sobol_quality_parameters(dimensions, MAX_LOG2_POINTS);
+    let results = [Sampler::owen(hash), Sampler::reference()]
+        .iter()
+        .map(|sampler| {
+            let failures = scrambled_net_failures(
+                |i, d, s| sampler.sample_u32(i, d, s),
+                dimensions,
+                &t,
+                &seeds,
+            );
+            (*sampler, failures)
+        })
+        .collect();
+    NetsCheck { t, results }
+}
+
+/// How many seeds give power-of-two prefixes of shuffled points that
+/// aren't (0,m,2)-nets.  See `check_shuffled_nets()`.
+#[derive(Debug, Clone)]
+pub struct ShuffledNetsCheck {
+    /// For `sobol::sample_owen_shuffled()`, for each prefix.  Item `m` is
+    /// for the first `2^m` points.
+    pub shuffled: Vec<usize>,
+
+    /// The control, with the index shuffled independently for each
+    /// dimension.
+    pub control: Vec<usize>,
+}
+
+impl ShuffledNetsCheck {
+    /// Whether every prefix of the shuffled points was a net.
+    pub fn passed(&self) -> bool {
+        self.shuffled.iter().all(|&f| f == 0)
+    }
+}
+
+/// Checks that every power-of-two prefix of `sobol::sample_owen_shuffled()`
+/// is a (0,m,2)-net in its first two dimensions, for `seed_count` seeds,
+/// with `nets::shuffled_prefixes_are_nets()`.
+///
+/// As a control, the same check is run with the index shuffled
+/// independently for each dimension, which should fail.
+pub fn check_shuffled_nets(seed_count: usize, seed: u64) -> ShuffledNetsCheck {
+    let seeds = rng::seeds(seed, seed_count.max(1));
+    ShuffledNetsCheck {
+        shuffled: prefix_net_failures(
+            |s| shuffled_prefixes_are_nets(s, MAX_LOG2_POINTS),
+            MAX_LOG2_POINTS,
+            &seeds,
+        ),
+        control: prefix_net_failures(
+            |s| independently_shuffled_prefixes_are_nets(s, MAX_LOG2_POINTS),
+            MAX_LOG2_POINTS,
+            &seeds,
+        ),
+    }
+}
+
+/// The discrepancies of one point set.  See `discrepancy_table()`.
+#[derive(Clone)]
+pub struct SamplerDiscrepancies<'a> {
+    pub sampler: Sampler<'a>,
+
+    /// How many seeds the discrepancies are averaged over.
+    pub seed_count: usize,
+
+    /// For each power-of-two point count.  Item `m` is for `2^m` points.
+    pub discrepancies: Vec<Discrepancies>,
+}
+
+/// Computes the discrepancies of the first `dimensions` dimensions of the
+/// Sobol sequence for power-of-two point counts: unscrambled, Owen
+/// scrambled with `sobol::FAST_HASH` (or `hash` if given), and with the
+/// reference Owen scramble.  The scrambled ones are averaged over
+/// `seed_count` seeds.
+pub fn discrepancy_table<'a>(
+    hash: Option<&'a SelectedHash>,
+    dimensions: u32,
+    seed_count: usize,
+    seed: u64,
+) -> Vec<SamplerDiscrepancies<'a>> {
+    let seeds = rng::seeds(seed, seed_count.max(1));
+    compared_samplers(hash, &seeds)
+        .iter()
+        .map(|(sampler, seeds)| SamplerDiscrepancies {
+            sampler: *sampler,
+            seed_count: seeds.len(),
+            discrepancies: discrepancies_by_point_count(
+                |i, d, s| sampler.sample(i, d, s),
+                MAX_LOG2_POINTS,
+                dimensions,
+                seeds,
+            ),
+        })
+        .collect()
+}
+
+/// The integration error of each of the convergence test functions.  See
+/// `convergence_table()`.
+#[derive(Clone)]
+pub struct ConvergenceTable<'a> {
+    pub samplers: Vec<Sampler<'a>>,
+
+    /// Each test function, with its convergence for each of `samplers`.
+    pub functions: Vec<(&'static TestFunction, Vec<Convergence>)>,
+}
+
+/// Computes the RMS integration error vs. point count of each of the
+/// convergence test functions: with unscrambled Sobol points, Owen
+/// scrambled with `sobol::FAST_HASH` (or `hash` if given), and with the
+/// reference Owen scramble.  The scrambled ones are averaged over
+/// `seed_count` seeds.
+pub fn convergence_table<'a>(
+    hash: Option<&'a SelectedHash>,
+    seed_count: usize,
+    seed: u64,
+) -> ConvergenceTable<'a> {
+    let seeds = rng::seeds(seed, seed_count.max(1));
+    let samplers = compared_samplers(hash, &seeds);
+    let functions = TEST_FUNCTIONS
+        .iter()
+        .map(|function| {
+            let results = samplers
+                .iter()
+                .map(|(sampler, seeds)| {
+                    convergence(
+                        function,
+                        |i, d, s| sampler.sample(i, d, s),
+                        CONVERGENCE_MAX_LOG2_POINTS,
+                        seeds,
+                    )
+                })
+                .collect();
+            (function, results)
+        })
+        .collect();
+    ConvergenceTable {
+        samplers: samplers.iter().map(|(sampler, _)| *sampler).collect(),
+        functions,
+    }
+}
+
+/// Computes the power spectrum of `point_count` points of a point set in
+/// the given pair of dimensions, averaged over `seed_count` seeds, with
+/// `spectrum::power_spectrum()`.
+pub fn point_set_spectrum(
+    sampler: &Sampler,
+    point_count: u32,
+    dims: (u32, u32),
+    seed_count: usize,
+    resolution: usize,
+    seed: u64,
+) -> Spectrum {
+    power_spectrum(
+        |i, d, s| sampler.sample(i, d, s),
+        point_count,
+        dims,
+        &rng::seeds(seed, seed_count.max(1)),
+        resolution,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashes;
+
+    #[test]
+    fn a_hash_compared_with_itself_has_no_difference() {
+        let hash = SelectedHash::Registered(hashes::get::<u32>("fast").unwrap());
+        let mut comparison = compare_hashes(&hash, &hash, 1 << 14, 3, false);
+        assert_eq!(comparison.a.score, comparison.b.score);
+        assert_eq!(comparison.difference, 0.0);
+        assert_eq!(comparison.difference_error, Some(0.0));
+        assert_eq!(comparison.significance(), Some((0.0, 1.0)));
+
+        comparison.difference = 0.25;
+        assert_eq!(comparison.significance(), Some((f64::INFINITY, 0.0)));
+    }
+
+    #[test]
+    fn comparison_differences_are_b_minus_a() {
+        let a = SelectedHash::Registered(hashes::get::<u32>("laine_karras").unwrap());
+        let b = SelectedHash::Registered(hashes::get::<u32>("reference").unwrap());
+        let comparison = compare_hashes(&a, &b, 1 << 14, 3, false);
+        assert_eq!(
+            comparison.difference,
+            comparison.b.score - comparison.a.score
+        );
+        assert!(comparison.difference < 0.0);
+        assert!(comparison.significance().unwrap().0 < 0.0);
+    }
+
+    #[test]
+    fn point_set_checks_pass_for_the_default_scramble() {
+        assert!(check_shuffled_nets(4, 1).passed());
+        assert!(check_nets(None, &[0, 1, 2], 4, 1).passed());
+    }
+
+    #[test]
+    #[should_panic]
+    fn exhaustive_checks_reject_zero_bits() {
+        let hash = SelectedHash::Registered(hashes::get::<u32>("fast").unwrap());
+        check_exhaustive(&hash, 0, 1, 1, false);
+    }
+}
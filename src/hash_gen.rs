@@ -1,4 +1,9 @@
-use rand::random;
+use std::fmt;
+
+use rand::Rng;
+
+use crate::compiled_hash::CompiledHash;
+use crate::word::Word;
 
 // A single operation in an Owen-scramble hash.
 //
@@ -6,50 +11,54 @@ use rand::random;
 // to mean "use the passed seed".  This is because for all
 // operations a constant of zero is either effectively a no-op,
 // or it's completely invalid for this kind of hash anyway.
-#[derive(Debug, Copy, Clone)]
-pub enum HashOp {
-    Nop,         // Do nothing
-    Xor(u32),    // x ^= constant
-    Add(u32),    // x += constant
-    Mul(u32),    // x *= odd_constant
-    ShlXor(u32), // x ^= x << constant[1, 31]
-    ShlAdd(u32), // x += x << constant[1, 31]
-    MulXor(u32), // x ^= x * even_constant
-    SeedMix,     // The super effective seed mixing approach.
+//
+// In the textual format (see `format_hash_ops()` and
+// `parse_hash_ops()`) that convention is made explicit: a
+// constant of zero is written as `seed`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HashOp<W = u32> {
+    Nop,       // Do nothing
+    Xor(W),    // x ^= constant
+    Add(W),    // x += constant
+    Mul(W),    // x *= odd_constant
+    ShlXor(W), // x ^= x << constant[1, bits - 1]
+    ShlAdd(W), // x += x << constant[1, bits - 1]
+    MulXor(W), // x ^= x * even_constant
+    SeedMix,   // The super effective seed mixing approach.
 }
 
-impl HashOp {
-    pub fn gen_random() -> HashOp {
+impl<W: Word> HashOp<W> {
+    pub fn gen_random<R: Rng + ?Sized>(rng: &mut R) -> HashOp<W> {
         // 1/4 chance of selecting the seed, otherwise random constant.
-        let constant = if (random::<u32>() & 0b11) == 0 {
-            0
+        let constant = if (rng.gen::<u32>() & 0b11) == 0 {
+            W::ZERO
         } else {
-            random::<u32>()
+            W::random(rng)
         };
 
-        match random::<u32>() % 8 {
+        match rng.gen::<u32>() % 8 {
             0 => HashOp::Add(constant),
             1 => {
-                if constant == 0 {
-                    HashOp::Mul(0)
+                if constant == W::ZERO {
+                    HashOp::Mul(W::ZERO)
                 } else {
-                    HashOp::Mul(constant | 1)
+                    HashOp::Mul(constant | W::ONE)
                 }
             }
-            2 => HashOp::MulXor(constant & !1),
+            2 => HashOp::MulXor(constant & !W::ONE),
             3 => HashOp::Xor(constant),
             4 => {
-                if constant == 0 {
-                    HashOp::ShlXor(0)
+                if constant == W::ZERO {
+                    HashOp::ShlXor(W::ZERO)
                 } else {
-                    HashOp::ShlXor((constant % 31) + 1)
+                    HashOp::ShlXor(random_shift(constant))
                 }
             }
             5 => {
-                if constant == 0 {
-                    HashOp::ShlAdd(0)
+                if constant == W::ZERO {
+                    HashOp::ShlAdd(W::ZERO)
                 } else {
-                    HashOp::ShlAdd((constant % 31) + 1)
+                    HashOp::ShlAdd(random_shift(constant))
                 }
             }
             6 => HashOp::SeedMix,
@@ -58,67 +67,67 @@ impl HashOp {
         }
     }
 
-    pub fn new_constant(&self) -> HashOp {
+    pub fn new_constant<R: Rng + ?Sized>(&self, rng: &mut R) -> HashOp<W> {
         match *self {
             HashOp::Nop => *self,
             HashOp::SeedMix => *self,
 
             HashOp::Xor(c) => {
-                if c == 0 {
+                if c == W::ZERO {
                     *self
                 } else {
-                    HashOp::Xor(random::<u32>())
+                    HashOp::Xor(W::random(rng))
                 }
             }
 
             HashOp::Add(c) => {
-                if c == 0 {
+                if c == W::ZERO {
                     *self
                 } else {
-                    HashOp::Add(random::<u32>())
+                    HashOp::Add(W::random(rng))
                 }
             }
 
             HashOp::Mul(c) => {
-                if c == 0 {
+                if c == W::ZERO {
                     *self
                 } else {
-                    HashOp::Mul(random::<u32>() | 1)
+                    HashOp::Mul(W::random(rng) | W::ONE)
                 }
             }
 
             HashOp::ShlXor(c) => {
-                if c == 0 {
+                if c == W::ZERO {
                     *self
                 } else {
-                    HashOp::ShlXor((random::<u32>() % 31) + 1)
+                    HashOp::ShlXor(random_shift(W::random(rng)))
                 }
             }
 
             HashOp::ShlAdd(c) => {
-                if c == 0 {
+                if c == W::ZERO {
                     *self
                 } else {
-                    HashOp::ShlAdd((random::<u32>() % 31) + 1)
+                    HashOp::ShlAdd(random_shift(W::random(rng)))
                 }
             }
 
             HashOp::MulXor(c) => {
-                if c == 0 {
+                if c == W::ZERO {
                     *self
                 } else {
-                    HashOp::MulXor(random::<u32>() & !1)
+                    HashOp::MulXor(W::random(rng) & !W::ONE)
                 }
             }
         }
     }
 
-    pub fn exec(&self, x: u32, seed: u32) -> u32 {
+    pub fn exec(&self, x: W, seed: W) -> W {
         match *self {
             HashOp::Nop => x,
 
             HashOp::Xor(c) => {
-                if c == 0 {
+                if c == W::ZERO {
                     x ^ seed
                 } else {
                     x ^ c
@@ -126,7 +135,7 @@ impl HashOp {
             }
 
             HashOp::Add(c) => {
-                if c == 0 {
+                if c == W::ZERO {
                     x.wrapping_add(seed)
                 } else {
                     x.wrapping_add(c)
@@ -134,32 +143,32 @@ impl HashOp {
             }
 
             HashOp::Mul(c) => {
-                if c == 0 {
-                    x.wrapping_mul(seed | 1)
+                if c == W::ZERO {
+                    x.wrapping_mul(seed | W::ONE)
                 } else {
                     x.wrapping_mul(c)
                 }
             }
 
             HashOp::ShlXor(c) => {
-                if c == 0 {
-                    x ^ (x << (seed & 0b11111))
+                if c == W::ZERO {
+                    x ^ (x << seed_shift(seed))
                 } else {
-                    x ^ (x << c)
+                    x ^ (x << c.to_u64() as u32)
                 }
             }
 
             HashOp::ShlAdd(c) => {
-                if c == 0 {
-                    x.wrapping_add(x << (seed & 0b11111))
+                if c == W::ZERO {
+                    x.wrapping_add(x << seed_shift(seed))
                 } else {
-                    x.wrapping_add(x << c)
+                    x.wrapping_add(x << c.to_u64() as u32)
                 }
             }
 
             HashOp::MulXor(c) => {
-                if c == 0 {
-                    x ^ x.wrapping_mul(seed & !1)
+                if c == W::ZERO {
+                    x ^ x.wrapping_mul(seed & !W::ONE)
                 } else {
                     x ^ x.wrapping_mul(c)
                 }
@@ -167,31 +176,470 @@ impl HashOp {
 
             HashOp::SeedMix => {
                 let a = x.wrapping_add(seed);
-                a.wrapping_mul((seed >> 16) | 1)
+                a.wrapping_mul((seed >> (W::BITS / 2)) | W::ONE)
             }
         }
     }
 
+    /// Same as `exec()`, except on `bits`-bit words instead of full
+    /// words.  `x` and `seed` must fit in `bits` bits, and so will the
+    /// result.
+    ///
+    /// Each op is reduced in the natural way: constants are truncated to
+    /// `bits` bits, shifting past the top of the word shifts everything
+    /// out, shift amounts taken from the seed are `seed % bits`, and
+    /// `SeedMix` multiplies by the upper half of the seed.  With `bits`
+    /// equal to the word size this is exactly `exec()`.
+    ///
+    /// Panics if `bits` is 0 or more than the word size.
+    pub fn exec_bits(&self, x: W, seed: W, bits: u32) -> W {
+        assert!(
+            (1..=W::BITS).contains(&bits),
+            "can't hash {}-bit words with a {}-bit hash",
+            bits,
+            W::BITS
+        );
+        let mask = if bits >= W::BITS {
+            !W::ZERO
+        } else {
+            !(!W::ZERO << bits)
+        };
+        debug_assert!(
+            x & !mask == W::ZERO && seed & !mask == W::ZERO,
+            "x and seed must fit in {} bits",
+            bits
+        );
+        let seed_shift = (seed.to_u64() % bits as u64) as u32;
+
+        // Since add, multiply, xor and left shift only ever carry
+        // upwards, doing them on full words and then masking gives the
+        // same result as doing them on `bits` bits.
+        let x = match *self {
+            HashOp::ShlXor(c) if c == W::ZERO => x ^ (x << seed_shift),
+            HashOp::ShlAdd(c) if c == W::ZERO => x.wrapping_add(x << seed_shift),
+            HashOp::SeedMix => {
+                let a = x.wrapping_add(seed);
+                a.wrapping_mul((seed >> (bits / 2)) | W::ONE)
+            }
+            _ => self.exec(x, seed),
+        };
+
+        x & mask
+    }
+
     pub fn uses_mul_and_seed(&self) -> bool {
         match *self {
             HashOp::Nop => false,
-            HashOp::Xor(c) => false,
-            HashOp::Add(c) => false,
-            HashOp::Mul(c) => c == 0,
-            HashOp::ShlXor(c) => false,
-            HashOp::ShlAdd(c) => false,
-            HashOp::MulXor(c) => c == 0,
+            HashOp::Xor(_) => false,
+            HashOp::Add(_) => false,
+            HashOp::Mul(c) => c == W::ZERO,
+            HashOp::ShlXor(_) => false,
+            HashOp::ShlAdd(_) => false,
+            HashOp::MulXor(c) => c == W::ZERO,
             HashOp::SeedMix => true,
         }
     }
 }
 
+/// The shift amount used by the shift ops when their constant is the
+/// seed: the low bits of the seed.
+#[inline(always)]
+fn seed_shift<W: Word>(seed: W) -> u32 {
+    seed.to_u64() as u32 & (W::BITS - 1)
+}
+
+/// Maps a random word to a shift amount in [1, bits - 1].
+fn random_shift<W: Word>(n: W) -> W {
+    W::from_u64((n.to_u64() % (W::BITS as u64 - 1)) + 1)
+}
+
 /// Runs a slice of `HashOp`s as a hash function on the given
 /// value with the given seed.
-pub fn exec_hash_slice(hash_ops: &[HashOp], x: u32, seed: u32) -> u32 {
+pub fn exec_hash_slice<W: Word>(hash_ops: &[HashOp<W>], x: W, seed: W) -> W {
     let mut x = x;
     for op in hash_ops.iter() {
         x = op.exec(x, seed);
     }
     x
 }
+
+/// Same as `exec_hash_slice()`, except on `bits`-bit words.  See
+/// `HashOp::exec_bits()`, including for when this panics.
+pub fn exec_hash_slice_bits<W: Word>(hash_ops: &[HashOp<W>], x: W, seed: W, bits: u32) -> W {
+    let mut x = x;
+    for op in hash_ops.iter() {
+        x = op.exec_bits(x, seed, bits);
+    }
+    x
+}
+
+/// Same as `exec_hash_slice()`, but runs the hash on a whole batch of
+/// inputs at once, writing the results to `outputs`.
+///
+/// `inputs` and `outputs` must be the same length.
+///
+/// The hash is compiled anew on every call.  When running many batches
+/// with the same seed, use `compiled_hash::BatchHash` instead.
+pub fn exec_hash_slice_batch<W: Word>(
+    hash_ops: &[HashOp<W>],
+    inputs: &[W],
+    seed: W,
+    outputs: &mut [W],
+) {
+    // For tiny batches compiling the hash isn't worth it.
+    if inputs.len() < 8 {
+        for (out, &x) in outputs.iter_mut().zip(inputs.iter()) {
+            *out = exec_hash_slice(hash_ops, x, seed);
+        }
+    } else {
+        outputs.copy_from_slice(inputs);
+        CompiledHash::new(hash_ops, seed).exec_batch(outputs);
+    }
+}
+
+//----------------------------------------------------------------------
+// Mutation and crossover, for searching.
+
+/// The maximum number of operations that `mutate_hash_ops()` and
+/// `crossover_hash_ops()` will produce.
+pub const MAX_HASH_OPS: usize = 8;
+
+/// Produces a randomly mutated copy of a hash.
+///
+/// The mutation is one of: a new constant for one of the operations,
+/// inserting a random operation, deleting an operation, swapping two
+/// operations, or replacing an operation with a random one.  The returned
+/// hash always differs from the passed one.
+pub fn mutate_hash_ops<W: Word, R: Rng + ?Sized>(
+    hash_ops: &[HashOp<W>],
+    rng: &mut R,
+) -> Vec<HashOp<W>> {
+    loop {
+        let mut ops = hash_ops.to_vec();
+        let len = ops.len();
+        match rng.gen::<u32>() % 5 {
+            0 if len > 0 => {
+                let i = rng.gen::<usize>() % len;
+                ops[i] = ops[i].new_constant(rng);
+            }
+            1 if len < MAX_HASH_OPS => {
+                ops.insert(rng.gen::<usize>() % (len + 1), HashOp::gen_random(rng));
+            }
+            2 if len > 1 => {
+                ops.remove(rng.gen::<usize>() % len);
+            }
+            3 if len > 1 => {
+                ops.swap(rng.gen::<usize>() % len, rng.gen::<usize>() % len);
+            }
+            4 if len > 0 => {
+                ops[rng.gen::<usize>() % len] = HashOp::gen_random(rng);
+            }
+            _ => {}
+        }
+
+        if ops[..] != hash_ops[..] {
+            return ops;
+        }
+    }
+}
+
+/// Produces a child of two hashes, via single-point crossover: the
+/// start of `a` followed by the end of `b`, each cut at a random point.
+pub fn crossover_hash_ops<W: Word, R: Rng + ?Sized>(
+    a: &[HashOp<W>],
+    b: &[HashOp<W>],
+    rng: &mut R,
+) -> Vec<HashOp<W>> {
+    let i = rng.gen::<usize>() % (a.len() + 1);
+    let j = rng.gen::<usize>() % (b.len() + 1);
+    let mut ops: Vec<HashOp<W>> = a[..i].iter().chain(b[j..].iter()).copied().collect();
+    ops.truncate(MAX_HASH_OPS);
+
+    if ops.is_empty() {
+        a.to_vec()
+    } else {
+        ops
+    }
+}
+
+//----------------------------------------------------------------------
+// Textual format.
+//
+// A hash is written as a sequence of operations separated by semicolons
+// or newlines, e.g. `shladd 2; mulxor 0xfe9b5742; seedmix`.  Each
+// operation is its lower-case name, followed by its constant for the
+// operations that have one.  The constant is either a number (decimal or
+// `0x`-prefixed hex) or `seed`, which means "use the passed seed".  A
+// literal zero is rejected, since it's ambiguous.  Everything after a `#`
+// on a line is a comment.
+
+impl<W: Word> fmt::Display for HashOp<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Hex for the "bit soup" constants, decimal for shift amounts.
+        fn hex<W: Word>(f: &mut fmt::Formatter, name: &str, c: W) -> fmt::Result {
+            if c == W::ZERO {
+                write!(f, "{} seed", name)
+            } else {
+                write!(f, "{} 0x{:0width$x}", name, c, width = W::BITS as usize / 4)
+            }
+        }
+        fn dec<W: Word>(f: &mut fmt::Formatter, name: &str, c: W) -> fmt::Result {
+            if c == W::ZERO {
+                write!(f, "{} seed", name)
+            } else {
+                write!(f, "{} {}", name, c)
+            }
+        }
+
+        match *self {
+            HashOp::Nop => write!(f, "nop"),
+            HashOp::Xor(c) => hex(f, "xor", c),
+            HashOp::Add(c) => hex(f, "add", c),
+            HashOp::Mul(c) => hex(f, "mul", c),
+            HashOp::ShlXor(c) => dec(f, "shlxor", c),
+            HashOp::ShlAdd(c) => dec(f, "shladd", c),
+            HashOp::MulXor(c) => hex(f, "mulxor", c),
+            HashOp::SeedMix => write!(f, "seedmix"),
+        }
+    }
+}
+
+impl<W: Word> std::str::FromStr for HashOp<W> {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<HashOp<W>, ParseError> {
+        let mut parts = text.split_whitespace();
+        let name = parts
+            .next()
+            .ok_or_else(|| ParseError("empty operation".into()))?;
+        let constant = parts.next();
+        if let Some(extra) = parts.next() {
+            return Err(ParseError(format!(
+                "unexpected \"{}\" in \"{}\"",
+                extra,
+                text.trim()
+            )));
+        }
+
+        // Parses the constant, with `seed` mapping to zero.
+        let parse_constant = || -> Result<W, ParseError> {
+            let c = constant
+                .ok_or_else(|| ParseError(format!("\"{}\" needs a constant or `seed`", name)))?;
+            if c == "seed" {
+                return Ok(W::ZERO);
+            }
+            let n = if let Some(hex) = c.strip_prefix("0x").or_else(|| c.strip_prefix("0X")) {
+                W::from_str_radix(hex, 16)
+            } else {
+                W::from_str_radix(c, 10)
+            }
+            .map_err(|_| ParseError(format!("invalid constant \"{}\"", c)))?;
+            if n == W::ZERO {
+                return Err(ParseError(format!(
+                    "a constant of zero is ambiguous in \"{}\", use `seed` instead",
+                    text.trim()
+                )));
+            }
+            Ok(n)
+        };
+        let no_constant = |op: HashOp<W>| -> Result<HashOp<W>, ParseError> {
+            match constant {
+                None => Ok(op),
+                Some(c) => Err(ParseError(format!(
+                    "\"{}\" doesn't take a constant, but got \"{}\"",
+                    name, c
+                ))),
+            }
+        };
+        let check = |ok: bool, c: W, requirement: &str| -> Result<W, ParseError> {
+            if c == W::ZERO || ok {
+                Ok(c)
+            } else {
+                Err(ParseError(format!(
+                    "the constant of \"{}\" must be {}",
+                    name, requirement
+                )))
+            }
+        };
+        let shift_range = format!("in [1, {}]", W::BITS - 1);
+
+        match name {
+            "nop" => no_constant(HashOp::Nop),
+            "seedmix" => no_constant(HashOp::SeedMix),
+            "xor" => Ok(HashOp::Xor(parse_constant()?)),
+            "add" => Ok(HashOp::Add(parse_constant()?)),
+            "mul" => {
+                let c = parse_constant()?;
+                Ok(HashOp::Mul(check(c.bit(0), c, "odd")?))
+            }
+            "shlxor" => {
+                let c = parse_constant()?;
+                let ok = c.to_u64() < W::BITS as u64;
+                Ok(HashOp::ShlXor(check(ok, c, &shift_range)?))
+            }
+            "shladd" => {
+                let c = parse_constant()?;
+                let ok = c.to_u64() < W::BITS as u64;
+                Ok(HashOp::ShlAdd(check(ok, c, &shift_range)?))
+            }
+            "mulxor" => {
+                let c = parse_constant()?;
+                Ok(HashOp::MulXor(check(!c.bit(0), c, "even")?))
+            }
+            _ => Err(ParseError(format!("unknown operation \"{}\"", name))),
+        }
+    }
+}
+
+/// Formats a slice of `HashOp`s in the textual hash format.
+pub fn format_hash_ops<W: Word>(hash_ops: &[HashOp<W>]) -> String {
+    hash_ops
+        .iter()
+        .map(|op| op.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Parses a hash in the textual hash format.
+pub fn parse_hash_ops<W: Word>(text: &str) -> Result<Vec<HashOp<W>>, ParseError> {
+    let mut hash_ops = Vec::new();
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap();
+        for op_text in line.split(';') {
+            if !op_text.trim().is_empty() {
+                hash_ops.push(op_text.parse()?);
+            }
+        }
+    }
+    Ok(hash_ops)
+}
+
+/// An error from parsing the textual hash format.
+#[derive(Debug, Clone)]
+pub struct ParseError(String);
+impl std::error::Error for ParseError {}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashes::{HASHES, HASHES_64};
+    use crate::rng::Pcg32;
+
+    #[test]
+    fn registered_hashes_round_trip() {
+        for hash in HASHES.iter() {
+            if let Some(ops) = hash.ops {
+                let text = format_hash_ops(ops);
+                assert_eq!(parse_hash_ops::<u32>(&text).unwrap(), ops, "{}", hash.name);
+            }
+        }
+        for hash in HASHES_64.iter() {
+            if let Some(ops) = hash.ops {
+                let text = format_hash_ops(ops);
+                assert_eq!(parse_hash_ops::<u64>(&text).unwrap(), ops, "{}", hash.name);
+            }
+        }
+    }
+
+    /// Round-trips random programs, built the way the search builds them,
+    /// and checks that every op was tried both with a constant and with
+    /// the seed.
+    fn check_random_round_trips<W: Word>() {
+        let mut rng = Pcg32::new(7);
+        let mut seen = std::collections::HashSet::new();
+        for _ in 0..100 {
+            let mut hash_ops: Vec<HashOp<W>> = (0..1 + rng.gen::<usize>() % 8)
+                .map(|_| HashOp::gen_random(&mut rng))
+                .collect();
+            for _ in 0..20 {
+                let text = format_hash_ops(&hash_ops);
+                assert_eq!(parse_hash_ops::<W>(&text).unwrap(), hash_ops, "{}", text);
+                for op in hash_ops.iter() {
+                    let op_text = op.to_string();
+                    let mut words = op_text.split(' ');
+                    seen.insert(match (words.next().unwrap(), words.next()) {
+                        (name, Some("seed")) => format!("{} seed", name),
+                        (name, Some(_)) => format!("{} constant", name),
+                        (name, None) => name.to_string(),
+                    });
+                }
+                hash_ops = mutate_hash_ops(&hash_ops, &mut rng);
+            }
+        }
+        for op in &[
+            "nop",
+            "seedmix",
+            "xor constant",
+            "xor seed",
+            "add constant",
+            "add seed",
+            "mul constant",
+            "mul seed",
+            "shlxor constant",
+            "shlxor seed",
+            "shladd constant",
+            "shladd seed",
+            "mulxor constant",
+            "mulxor seed",
+        ] {
+            assert!(seen.contains(*op), "{}", op);
+        }
+    }
+
+    #[test]
+    fn random_hashes_round_trip_u32() {
+        check_random_round_trips::<u32>();
+    }
+
+    #[test]
+    fn random_hashes_round_trip_u64() {
+        check_random_round_trips::<u64>();
+    }
+
+    #[test]
+    fn malformed_hashes_are_rejected() {
+        for text in &[
+            "nosuchop 3",
+            "mulxor",
+            "shladd x",
+            "shladd 32",
+            "mul 0x2",
+            "mulxor 0x3",
+            "xor 0",
+            "seedmix 5",
+            "add 1 2",
+        ] {
+            assert!(parse_hash_ops::<u32>(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn full_width_exec_bits_is_exec() {
+        let ops = parse_hash_ops::<u32>("shladd 2; mulxor 0xfe9b5742; seedmix").unwrap();
+        for &(x, seed) in &[(0, 0), (1, 2), (0x1234_5678, 0x9abc_def0), (!0, !0)] {
+            assert_eq!(
+                exec_hash_slice_bits(&ops, x, seed, 32),
+                exec_hash_slice(&ops, x, seed)
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn exec_bits_rejects_zero_bits() {
+        let ops = parse_hash_ops::<u32>("seedmix").unwrap();
+        ops[0].exec_bits(0, 0, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn exec_bits_rejects_more_bits_than_the_word() {
+        let ops = parse_hash_ops::<u32>("seedmix").unwrap();
+        ops[0].exec_bits(0, 0, 33);
+    }
+}
@@ -0,0 +1,435 @@
+//! A registry of named Owen-scramble hashes.
+//!
+//! All of the hashes here are Laine-Karras style hashes: they operate on
+//! bit-reversed values, where each bit should only affect higher bits.
+//! That's the form `measure_stats()` expects, and the form that
+//! `sobol::owen_scramble_hash_u32()` wraps into a proper Owen scramble.
+//!
+//! Each word size has its own registry, see `HashWord`.
+
+use crate::compiled_hash::BatchHash;
+use crate::hash_gen::{
+    exec_hash_slice, exec_hash_slice_batch, exec_hash_slice_bits, format_hash_ops, parse_hash_ops,
+    HashOp,
+};
+use crate::sobol;
+use crate::stats::batch_hash;
+use crate::word::Word;
+
+/// A named scramble hash.
+pub struct ScrambleHash<W: 'static = u32> {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub source: &'static str, // Link to where the hash comes from, or a note if it's from here.
+    pub hash: fn(W, W) -> W,  // (input, seed) -> output
+    pub hash_batch: fn(&[W], W, &mut [W]), // Same as `hash`, on many inputs at once.
+    pub ops: Option<&'static [HashOp<W>]>, // The same hash, as `HashOp`s, if expressible that way.
+}
+
+/// Word sizes that have a registry of hashes.
+pub trait HashWord: Word {
+    /// All of the registered hashes on this word size.
+    const HASHES: &'static [ScrambleHash<Self>];
+
+    /// The name of the hash to use when none is specified.
+    const DEFAULT_HASH: &'static str;
+}
+
+impl HashWord for u32 {
+    const HASHES: &'static [ScrambleHash<u32>] = HASHES;
+    const DEFAULT_HASH: &'static str = "fixed";
+}
+
+impl HashWord for u64 {
+    const HASHES: &'static [ScrambleHash<u64>] = HASHES_64;
+    const DEFAULT_HASH: &'static str = "fixed";
+}
+
+/// All of the registered 32-bit hashes.
+pub const HASHES: &[ScrambleHash] = &[
+    ScrambleHash {
+        name: "reference",
+        description: "High quality reference Owen scramble, performed on reversed bits.",
+        source: "https://psychopath.io/post/2021_01_30_building_a_better_lk_hash",
+        hash: reference,
+        hash_batch: reference_batch,
+        ops: None,
+    },
+    ScrambleHash {
+        name: "laine_karras",
+        description: "Original Laine-Karras hash.",
+        source: "https://psychopath.io/post/2021_01_30_building_a_better_lk_hash",
+        hash: laine_karras,
+        hash_batch: laine_karras_batch,
+        ops: Some(&[
+            HashOp::Add(0),
+            HashOp::MulXor(0x6c50b47c),
+            HashOp::MulXor(0xb82f1e52),
+            HashOp::MulXor(0xc7afe638),
+            HashOp::MulXor(0x8d22f6e6),
+        ]),
+    },
+    ScrambleHash {
+        name: "v2",
+        description: "\"Improved\" version 2.  Not actually that good.",
+        source: "https://psychopath.io/post/2021_01_02_sobol_sampling_take_2",
+        hash: v2,
+        hash_batch: v2_batch,
+        ops: Some(&[
+            HashOp::Add(0),
+            HashOp::Xor(0xdc967795),
+            HashOp::Mul(0x97b754b7),
+            HashOp::Xor(0x866350b1),
+            HashOp::Mul(0x9e3779cd),
+        ]),
+    },
+    ScrambleHash {
+        name: "fast",
+        description: "Fast, reasonable quality.  Has the issue that Matt Pharr found.",
+        source: "https://psychopath.io/post/2021_01_30_building_a_better_lk_hash",
+        hash: fast,
+        hash_batch: fast_batch,
+        ops: Some(&[
+            HashOp::ShlAdd(2),
+            HashOp::MulXor(0xfe9b5742),
+            HashOp::Add(0),
+            HashOp::Mul(0),
+        ]),
+    },
+    ScrambleHash {
+        name: "medium",
+        description: "Medium-fast, good quality.  Has the issue that Matt Pharr found.",
+        source: "https://psychopath.io/post/2021_01_30_building_a_better_lk_hash",
+        hash: medium,
+        hash_batch: medium_batch,
+        ops: Some(&[
+            HashOp::Mul(0x788aeeed),
+            HashOp::MulXor(0x41506a02),
+            HashOp::Add(0),
+            HashOp::Mul(0),
+            HashOp::MulXor(0x7483dc64),
+        ]),
+    },
+    ScrambleHash {
+        name: "fixed",
+        description: "From the updated post, fixing the issue that Matt Pharr found.",
+        source: "https://psychopath.io/post/2021_01_30_building_a_better_lk_hash",
+        hash: fixed,
+        hash_batch: fixed_batch,
+        ops: Some(&[
+            HashOp::MulXor(0x3d20adea),
+            HashOp::SeedMix,
+            HashOp::MulXor(0x05526c56),
+            HashOp::MulXor(0x53a22864),
+        ]),
+    },
+];
+
+/// All of the registered 64-bit hashes.
+pub const HASHES_64: &[ScrambleHash<u64>] = &[
+    ScrambleHash {
+        name: "reference",
+        description: "High quality reference Owen scramble, performed on reversed bits.",
+        source: "https://psychopath.io/post/2021_01_30_building_a_better_lk_hash",
+        hash: reference,
+        hash_batch: reference_batch,
+        ops: None,
+    },
+    ScrambleHash {
+        name: "fixed",
+        description: "The 32-bit \"fixed\" hash widened to 64 bits, by repeating each constant.",
+        source: "Derived in this repository, not from the blog post.",
+        hash: fixed_64,
+        hash_batch: fixed_64_batch,
+        ops: Some(&[
+            HashOp::MulXor(0x3d20adea3d20adea),
+            HashOp::SeedMix,
+            HashOp::MulXor(0x05526c5605526c56),
+            HashOp::MulXor(0x53a2286453a22864),
+        ]),
+    },
+];
+
+/// Looks up a registered hash by name.
+pub fn get<W: HashWord>(name: &str) -> Option<&'static ScrambleHash<W>> {
+    W::HASHES.iter().find(|h| h.name == name)
+}
+
+/// Comma-separated list of the registered hash names, for error messages
+/// and help text.
+pub fn names<W: HashWord>() -> String {
+    W::HASHES
+        .iter()
+        .map(|h| h.name)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A hash chosen by the user: either a registered hash or a program of
+/// `HashOp`s in the textual hash format.
+#[derive(Clone)]
+pub enum SelectedHash<W: 'static = u32> {
+    Registered(&'static ScrambleHash<W>),
+    Program(Vec<HashOp<W>>),
+}
+
+impl<W: HashWord> SelectedHash<W> {
+    /// Looks up `text` as a registered hash name, and otherwise parses it
+    /// as a `HashOp` program.
+    pub fn from_text(text: &str) -> Result<SelectedHash<W>, String> {
+        if let Some(h) = get(text.trim()) {
+            return Ok(SelectedHash::Registered(h));
+        }
+        match parse_hash_ops(text) {
+            Ok(ops) if !ops.is_empty() => Ok(SelectedHash::Program(ops)),
+            Ok(_) => Err("empty hash".into()),
+            Err(e) => Err(format!(
+                "\"{}\" is neither a registered hash ({}) nor a valid hash program: {}",
+                text.trim(),
+                names::<W>(),
+                e
+            )),
+        }
+    }
+
+    /// A short name for display.  All programs are just "program", see
+    /// `file_name()`.
+    pub fn name(&self) -> &str {
+        match self {
+            SelectedHash::Registered(h) => h.name,
+            SelectedHash::Program(_) => "program",
+        }
+    }
+
+    /// A short name, suitable for filenames.  Programs are told apart by a
+    /// hash of their text, like "program_1a2b3c4d".
+    pub fn file_name(&self) -> String {
+        match self {
+            SelectedHash::Registered(h) => h.name.to_string(),
+            SelectedHash::Program(ops) => {
+                use std::hash::Hasher;
+                let mut hasher = siphasher::sip::SipHasher13::new_with_keys(0, 0);
+                hasher.write(format_hash_ops(ops).as_bytes());
+                format!("program_{:08x}", hasher.finish() as u32)
+            }
+        }
+    }
+
+    /// A human-readable description.
+    pub fn description(&self) -> String {
+        match self {
+            SelectedHash::Registered(h) => h.description.into(),
+            SelectedHash::Program(ops) => format_hash_ops(ops),
+        }
+    }
+
+    /// The hash as `HashOp`s, if it's expressible that way.
+    pub fn ops(&self) -> Option<&[HashOp<W>]> {
+        match self {
+            SelectedHash::Registered(h) => h.ops,
+            SelectedHash::Program(ops) => Some(ops),
+        }
+    }
+
+    #[inline]
+    pub fn exec(&self, n: W, seed: W) -> W {
+        match self {
+            SelectedHash::Registered(h) => (h.hash)(n, seed),
+            SelectedHash::Program(ops) => exec_hash_slice(ops, n, seed),
+        }
+    }
+
+    /// Runs the hash on `bits`-bit words.
+    ///
+    /// Hashes expressible as `HashOp`s use `exec_hash_slice_bits()`.  Other
+    /// hashes are run on full words and truncated, which is still a valid
+    /// `bits`-bit Laine-Karras style hash since bits only affect higher
+    /// bits.
+    ///
+    /// Panics if `bits` is 0 or more than the word size.
+    pub fn exec_bits(&self, n: W, seed: W, bits: u32) -> W {
+        assert!(
+            (1..=W::BITS).contains(&bits),
+            "can't hash {}-bit words with a {}-bit hash",
+            bits,
+            W::BITS
+        );
+        match self.ops() {
+            Some(ops) => exec_hash_slice_bits(ops, n, seed, bits),
+            None => self.exec(n, seed) & (!W::ZERO >> (W::BITS - bits)),
+        }
+    }
+
+    pub fn exec_batch(&self, inputs: &[W], seed: W, outputs: &mut [W]) {
+        match self {
+            SelectedHash::Registered(h) => (h.hash_batch)(inputs, seed, outputs),
+            SelectedHash::Program(ops) => exec_hash_slice_batch(ops, inputs, seed, outputs),
+        }
+    }
+
+    /// Same as `exec_batch()`, but for running many batches, such as in
+    /// `measure_stats()`.  Programs are compiled once per seed rather than
+    /// once per batch, see `BatchHash`.
+    pub fn batch_hasher(&self) -> impl Fn(&[W], W, &mut [W]) + Sync + '_ {
+        let program = match self {
+            SelectedHash::Registered(_) => None,
+            SelectedHash::Program(ops) => Some(BatchHash::new(ops)),
+        };
+        move |inputs: &[W], seed: W, outputs: &mut [W]| match program {
+            Some(ref program) => program.exec(inputs, seed, outputs),
+            None => self.exec_batch(inputs, seed, outputs),
+        }
+    }
+}
+
+//----------------------------------------------------------------------
+
+pub fn reference<W: Word>(n: W, seed: W) -> W {
+    sobol::owen_scramble_reference(n.reverse_bits(), seed).reverse_bits()
+}
+
+pub fn laine_karras(n: u32, seed: u32) -> u32 {
+    let mut n = n.wrapping_add(seed);
+    n ^= n.wrapping_mul(0x6c50b47c);
+    n ^= n.wrapping_mul(0xb82f1e52);
+    n ^= n.wrapping_mul(0xc7afe638);
+    n ^= n.wrapping_mul(0x8d22f6e6);
+    n
+}
+
+pub fn v2(n: u32, seed: u32) -> u32 {
+    let mut n = n.wrapping_add(seed);
+    n ^= 0xdc967795;
+    n = n.wrapping_mul(0x97b754b7);
+    n ^= 0x866350b1;
+    n = n.wrapping_mul(0x9e3779cd);
+    n
+}
+
+pub fn fast(n: u32, seed: u32) -> u32 {
+    let mut n = n.wrapping_add(n << 2);
+    n ^= n.wrapping_mul(0xfe9b5742);
+    n = n.wrapping_add(seed);
+    n = n.wrapping_mul(seed | 1);
+    n
+}
+
+pub fn medium(n: u32, seed: u32) -> u32 {
+    let mut n = n.wrapping_mul(0x788aeeed);
+    n ^= n.wrapping_mul(0x41506a02);
+    n = n.wrapping_add(seed);
+    n = n.wrapping_mul(seed | 1);
+    n ^= n.wrapping_mul(0x7483dc64);
+    n
+}
+
+pub fn fixed(n: u32, seed: u32) -> u32 {
+    let mut n = n ^ n.wrapping_mul(0x3d20adea);
+    n = n.wrapping_add(seed);
+    n = n.wrapping_mul((seed >> 16) | 1);
+    n ^= n.wrapping_mul(0x05526c56);
+    n ^= n.wrapping_mul(0x53a22864);
+    n
+}
+
+pub fn fixed_64(n: u64, seed: u64) -> u64 {
+    let mut n = n ^ n.wrapping_mul(0x3d20adea3d20adea);
+    n = n.wrapping_add(seed);
+    n = n.wrapping_mul((seed >> 32) | 1);
+    n ^= n.wrapping_mul(0x05526c5605526c56);
+    n ^= n.wrapping_mul(0x53a2286453a22864);
+    n
+}
+
+//----------------------------------------------------------------------
+// Batch versions of the above, for `measure_stats()`.
+
+pub fn reference_batch<W: Word>(inputs: &[W], seed: W, outputs: &mut [W]) {
+    batch_hash(reference, inputs, seed, outputs)
+}
+
+pub fn laine_karras_batch(inputs: &[u32], seed: u32, outputs: &mut [u32]) {
+    batch_hash(laine_karras, inputs, seed, outputs)
+}
+
+pub fn v2_batch(inputs: &[u32], seed: u32, outputs: &mut [u32]) {
+    batch_hash(v2, inputs, seed, outputs)
+}
+
+pub fn fast_batch(inputs: &[u32], seed: u32, outputs: &mut [u32]) {
+    batch_hash(fast, inputs, seed, outputs)
+}
+
+pub fn medium_batch(inputs: &[u32], seed: u32, outputs: &mut [u32]) {
+    batch_hash(medium, inputs, seed, outputs)
+}
+
+pub fn fixed_batch(inputs: &[u32], seed: u32, outputs: &mut [u32]) {
+    batch_hash(fixed, inputs, seed, outputs)
+}
+
+pub fn fixed_64_batch(inputs: &[u64], seed: u64, outputs: &mut [u64]) {
+    batch_hash(fixed_64, inputs, seed, outputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_ops<W: HashWord>() {
+        let inputs = [
+            W::ZERO,
+            W::ONE,
+            !W::ZERO,
+            W::from_u64(0x0123_4567_89ab_cdef),
+        ];
+        for hash in W::HASHES.iter() {
+            if let Some(ops) = hash.ops {
+                for &seed in inputs.iter() {
+                    for &n in inputs.iter() {
+                        assert_eq!(
+                            (hash.hash)(n, seed),
+                            exec_hash_slice(ops, n, seed),
+                            "{}",
+                            hash.name
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn registered_hashes_match_their_ops() {
+        check_ops::<u32>();
+        check_ops::<u64>();
+    }
+
+    #[test]
+    fn programs_have_distinct_file_names() {
+        let a = SelectedHash::<u32>::from_text("shladd 2; seedmix").unwrap();
+        let b = SelectedHash::<u32>::from_text("shladd 3; seedmix").unwrap();
+        let a_again = SelectedHash::<u32>::from_text("shladd 0x2;  seedmix").unwrap();
+        assert_eq!(a.name(), "program");
+        assert!(a.file_name().starts_with("program_"));
+        assert_ne!(a.file_name(), b.file_name());
+        assert_eq!(a.file_name(), a_again.file_name());
+
+        let fast = SelectedHash::<u32>::from_text("fast").unwrap();
+        assert_eq!(fast.file_name(), "fast");
+    }
+
+    #[test]
+    fn default_hashes_have_ops() {
+        assert!(get::<u32>(u32::DEFAULT_HASH).unwrap().ops.is_some());
+        assert!(get::<u64>(u64::DEFAULT_HASH).unwrap().ops.is_some());
+    }
+
+    #[test]
+    #[should_panic]
+    fn exec_bits_rejects_zero_bits_without_ops() {
+        // The reference hash has no ops, so this is the truncating path.
+        let hash = SelectedHash::Registered(get::<u32>("reference").unwrap());
+        hash.exec_bits(0, 0, 0);
+    }
+}
@@ -0,0 +1,133 @@
+//! Checks that the scrambles of different dimensions of a point set are
+//! independent of each other.
+//!
+//! For a handful of fixed sample indices, the coordinates of each pair of
+//! dimensions over many seeds are binned into a 2D histogram, and checked
+//! with a chi-squared test of independence.  This is deliberately not a
+//! test of uniformity, which is a property of the scramble hash rather
+//! than of how the dimensions are seeded.  Index 0 is always 0 before
+//! scrambling, so there this directly compares the scrambles of the two
+//! dimensions.
+
+use rayon::prelude::*;
+
+/// The sample indices that are checked.
+pub const INDICES: [u32; 4] = [0, 1, 5, 1000];
+
+/// The largest deviation from independence that passes, in standard
+/// deviations of the chi-squared statistic.  The test is run many times,
+/// so this needs to be well clear of the typical maximum.
+pub const MAX_DEVIATION: f64 = 6.0;
+
+/// The number of histogram bins along each dimension.
+const BINS: usize = 16;
+
+/// How far a pair of dimensions is from independent.
+#[derive(Debug, Copy, Clone)]
+pub struct PairDeviation {
+    /// In standard deviations of the chi-squared statistic.
+    pub deviation: f64,
+    pub dimensions: (u32, u32),
+}
+
+/// Finds the pair of the first `dimensions` dimensions whose coordinates
+/// deviate the most from independence over the given seeds, for each of
+/// `INDICES`.
+///
+/// `scramble` uses its seed as-is for the dimension, and `dimension_seed`
+/// derives that from the seed of the point set, e.g.
+/// `sobol::dimension_seed()`.
+pub fn worst_deviations<F, G>(
+    scramble: F,
+    dimension_seed: G,
+    dimensions: u32,
+    seeds: &[u32],
+) -> Vec<PairDeviation>
+where
+    F: Fn(u32, u32, u32) -> f32 + Sync, // (sample_index, dimension, seed) -> coordinate
+    G: Fn(u32, u32) -> u32 + Sync,      // (dimension, seed) -> dimension seed
+{
+    let pairs: Vec<(u32, u32)> = (0..dimensions)
+        .flat_map(|a| ((a + 1)..dimensions).map(move |b| (a, b)))
+        .collect();
+    INDICES
+        .iter()
+        .map(|&index| {
+            pairs
+                .par_iter()
+                .map(|&(a, b)| {
+                    let mut histogram = [[0usize; BINS]; BINS];
+                    for &s in seeds.iter() {
+                        let x = scramble(index, a, dimension_seed(a, s));
+                        let y = scramble(index, b, dimension_seed(b, s));
+                        let bin = |v: f32| ((v * BINS as f32) as usize).min(BINS - 1);
+                        histogram[bin(y)][bin(x)] += 1;
+                    }
+                    PairDeviation {
+                        deviation: independence_deviation(&histogram),
+                        dimensions: (a, b),
+                    }
+                })
+                .reduce(
+                    || PairDeviation {
+                        deviation: f64::NEG_INFINITY,
+                        dimensions: (0, 0),
+                    },
+                    |x, y| if y.deviation > x.deviation { y } else { x },
+                )
+        })
+        .collect()
+}
+
+/// The chi-squared test of independence of a 2D histogram, against the
+/// product of its observed marginal distributions, in standard deviations
+/// of the chi-squared statistic.
+fn independence_deviation(histogram: &[[usize; BINS]; BINS]) -> f64 {
+    let row_sums: Vec<f64> = histogram
+        .iter()
+        .map(|row| row.iter().sum::<usize>() as f64)
+        .collect();
+    let col_sums: Vec<f64> = (0..BINS)
+        .map(|x| histogram.iter().map(|row| row[x]).sum::<usize>() as f64)
+        .collect();
+    let n = row_sums.iter().sum::<f64>();
+    let mut chi_squared = 0.0;
+    for (row, &row_sum) in histogram.iter().zip(row_sums.iter()) {
+        for (&count, &col_sum) in row.iter().zip(col_sums.iter()) {
+            let expected = row_sum * col_sum / n;
+            if expected > 0.0 {
+                let d = count as f64 - expected;
+                chi_squared += d * d / expected;
+            }
+        }
+    }
+    let occupied = |sums: &[f64]| sums.iter().filter(|&&s| s > 0.0).count();
+    let dof = ((occupied(&row_sums) - 1) * (occupied(&col_sums) - 1)) as f64;
+    if dof == 0.0 {
+        // One of the coordinates is constant, so they're trivially
+        // independent.
+        return 0.0;
+    }
+    (chi_squared - dof) / (2.0 * dof).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rng, sobol};
+
+    #[test]
+    fn derived_seeds_pass_and_shared_seeds_fail() {
+        let seeds = rng::seeds(1, 4096);
+        let scramble = |i, d, s| sobol::sample_owen_fast(i, d, s);
+
+        let derived = worst_deviations(scramble, sobol::dimension_seed, 4, &seeds);
+        assert_eq!(derived.len(), INDICES.len());
+        assert!(derived.iter().all(|d| d.deviation < MAX_DEVIATION));
+
+        // Index 0 is 0 in every dimension before scrambling, so a shared
+        // seed gives identical coordinates.
+        let shared = worst_deviations(scramble, |_, s| s, 4, &seeds);
+        assert!(shared[0].deviation > MAX_DEVIATION);
+    }
+}
@@ -0,0 +1,55 @@
+//! Owen scrambling of the Sobol sequence with Laine-Karras style hashes,
+//! and the tools used to search for and evaluate those hashes.
+//!
+//! The main public API is:
+//!
+//! * `sobol`: the Sobol sequence and the Owen scrambling functions.
+//! * `hash_gen`: hashes as sequences of simple operations, which is how
+//!   they're searched for.
+//! * `stats`: measuring and scoring the quality of a hash.
+//!
+//! The experiments of the `sample_test` binary are in `experiments`, and
+//! the rest of the modules are the machinery behind them.
+
+#![allow(clippy::needless_range_loop)]
+
+pub mod bench;
+pub mod compiled_hash;
+pub mod convergence;
+pub mod direction_numbers;
+pub mod discrepancy;
+pub mod exhaustive;
+pub mod experiments;
+pub mod hash_gen;
+pub mod hashes;
+pub mod independence;
+pub mod nets;
+pub mod rng;
+pub mod samplers;
+pub mod samples_image;
+pub mod search;
+pub mod sobol;
+pub mod spectrum;
+pub mod stats;
+pub mod stats_diff;
+pub mod stats_file;
+pub mod stats_image;
+pub mod word;
+
+/// A general purpose 32-bit hash, for decorrelating seeds.  This is not an
+/// Owen scramble.
+pub fn hash_u32(n: u32, seed: u32) -> u32 {
+    // Seeding.
+    let mut n = 0x6217c6e1 ^ n.wrapping_add(seed.wrapping_mul(0x9e3779b9));
+
+    // From https://github.com/skeeto/hash-prospector
+    n ^= n >> 17;
+    n = n.wrapping_mul(0xed5ad4bb);
+    n ^= n >> 11;
+    n = n.wrapping_mul(0xac4c1b51);
+    n ^= n >> 15;
+    n = n.wrapping_mul(0x31848bab);
+    n ^= n >> 14;
+
+    n
+}
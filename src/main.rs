@@ -1,14 +1,25 @@
-#![allow(unused)]
-
-mod hash_gen;
-mod sobol;
-mod stats;
+#![allow(clippy::needless_range_loop)]
 
 use std::fs::File;
 use std::io::Write;
+use std::path::{Path, PathBuf};
 
-use hash_gen::{exec_hash_slice, HashOp};
-use stats::{measure_stats, print_stats, write_stats_image, Stats, STATS_ZERO};
+use sample_test::experiments::DiffInput;
+use sample_test::hash_gen::{format_hash_ops, HashOp};
+use sample_test::hashes::{HashWord, SelectedHash};
+use sample_test::samplers::Sampler;
+use sample_test::stats::{
+    print_stats, print_stats_estimate, score_stats, score_stats_bits, Stats, MIN_ERROR_ROUNDS,
+};
+use sample_test::stats_diff::Verdict;
+use sample_test::stats_image::{
+    write_stats_diff_image, write_stats_image, StatsImageMode, StatsImageOptions,
+};
+use sample_test::word::Word;
+use sample_test::{
+    bench, compiled_hash, convergence, discrepancy, exhaustive, experiments, hashes, independence,
+    samples_image, search, sobol, spectrum, stats_file,
+};
 
 fn main() {
     // Set rayon per-thread stack size, because by default it's too small
@@ -20,336 +31,1622 @@ fn main() {
 
     // Parse command line arguments.
     let args = clap::App::new("Sample Testing")
-        .version("0.123456789")
-        .about("")
-        .arg(clap::Arg::with_name("test").long("test"))
-        .arg(clap::Arg::with_name("search").long("search"))
-        .arg(clap::Arg::with_name("reference").long("ref"))
-        .arg(
-            clap::Arg::with_name("number")
-                .takes_value(true)
-                .required(false),
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Experiments with hash-based Owen scrambling of the Sobol sequence")
+        .setting(clap::AppSettings::SubcommandRequiredElseHelp)
+        .setting(clap::AppSettings::VersionlessSubcommands)
+        //------------------------------------------------------------
+        // Hash testing and searching.
+        .subcommand(
+            clap::SubCommand::with_name("list-hashes")
+                .about("Lists the registered hashes")
+                .arg(bits_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("test")
+                .about("Measures the statistics of a hash, and writes them to stats.png")
+                .args(&hash_args())
+                .arg(bits_arg())
+                .arg(rounds_arg())
+                .arg(seed_arg())
+                .arg(output_dir_arg())
+                .args(&stats_image_args())
+                .arg(save_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("compare")
+                .about("Tests whether one hash is statistically better than another")
+                .arg(
+                    clap::Arg::with_name("hash_a")
+                        .value_name("HASH_A")
+                        .required(true)
+                        .help("The first hash, in the same format as --hash"),
+                )
+                .arg(
+                    clap::Arg::with_name("hash_b")
+                        .value_name("HASH_B")
+                        .required(true)
+                        .help("The second hash, in the same format as --hash"),
+                )
+                .arg(bits_arg())
+                .arg(rounds_arg())
+                .arg(seed_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("diff")
+                .about(
+                    "Compares the stats of two hashes output bit by output bit, and writes \
+                     both stats and their difference to diff.png",
+                )
+                .arg(
+                    clap::Arg::with_name("a")
+                        .value_name("A")
+                        .required(true)
+                        .help(
+                            "The first hash, in the same format as --hash or as a file \
+                             containing it, or stats saved with --save",
+                        ),
+                )
+                .arg(
+                    clap::Arg::with_name("b")
+                        .value_name("B")
+                        .required(true)
+                        .help("The second hash or saved stats, in the same format as A"),
+                )
+                .arg(bits_arg())
+                .arg(rounds_arg())
+                .arg(seed_arg())
+                .arg(output_dir_arg())
+                .args(&stats_image_args()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("search")
+                .about(
+                    "Searches for better hashes, starting from --hash as a template, and \
+                     writes the stats of the best ones to candidate_NN.png",
+                )
+                .args(&hash_args())
+                .arg(bits_arg())
+                .arg(
+                    clap::Arg::with_name("rounds")
+                        .long("rounds")
+                        .takes_value(true)
+                        .value_name("N")
+                        .help("How many hashes to try [default: 10000]"),
+                )
+                .arg(
+                    clap::Arg::with_name("stat_rounds")
+                        .long("stat-rounds")
+                        .takes_value(true)
+                        .value_name("N")
+                        .help(
+                            "How many rounds of measurements to score each hash with \
+                             [default: 4194304]",
+                        ),
+                )
+                .arg(
+                    clap::Arg::with_name("strategy")
+                        .long("strategy")
+                        .takes_value(true)
+                        .possible_values(search::Strategy::NAMES)
+                        .help(
+                            "Hash search strategy [default: evolve].  A resumed search keeps \
+                             the strategy of its checkpoint",
+                        ),
+                )
+                .arg(
+                    clap::Arg::with_name("checkpoint")
+                        .long("checkpoint")
+                        .takes_value(true)
+                        .value_name("FILE")
+                        .help("File to periodically save the hash search state to"),
+                )
+                .arg(
+                    clap::Arg::with_name("checkpoint_every")
+                        .long("checkpoint-every")
+                        .takes_value(true)
+                        .value_name("N")
+                        .default_value("10")
+                        .help("How many search rounds between checkpoints"),
+                )
+                .arg(
+                    clap::Arg::with_name("resume")
+                        .long("resume")
+                        .takes_value(true)
+                        .value_name("FILE")
+                        .conflicts_with_all(&["hash", "hash_file", "seed", "strategy"])
+                        .help(
+                            "Resume a hash search from a checkpoint file.  Unless --checkpoint \
+                             is also given, checkpoints are saved back to the same file",
+                        ),
+                )
+                .arg(seed_arg())
+                .arg(output_dir_arg())
+                .args(&stats_image_args()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("exhaustive")
+                .about(
+                    "Computes exact stats of a reduced bit-width version of a hash, compares \
+                     them against the sampled stats, and writes them to exhaustive.png",
+                )
+                .args(&hash_args())
+                .arg(
+                    clap::Arg::with_name("width")
+                        .long("width")
+                        .takes_value(true)
+                        .value_name("BITS")
+                        .required(true)
+                        .help("Bit width to reduce the hash to"),
+                )
+                .arg(seeds_arg("16"))
+                .arg(seed_arg())
+                .arg(output_dir_arg())
+                .args(&stats_image_args())
+                .arg(save_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("render")
+                .about(
+                    "Prints stats saved with --save, and writes them as images named after \
+                     the files",
+                )
+                .arg(
+                    clap::Arg::with_name("files")
+                        .value_name("FILE")
+                        .required(true)
+                        .multiple(true)
+                        .help("Saved stats, in .json or .csv format"),
+                )
+                .arg(output_dir_arg())
+                .args(&stats_image_args()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("bench")
+                .about("Benchmarks interpreted vs. compiled evaluation of a hash")
+                .args(&hash_args())
+                .arg(
+                    clap::Arg::with_name("count")
+                        .long("count")
+                        .takes_value(true)
+                        .value_name("N")
+                        .default_value("100000000")
+                        .help("How many times to evaluate the hash"),
+                )
+                .arg(seed_arg()),
+        )
+        //------------------------------------------------------------
+        // Point sets.
+        .subcommand(
+            clap::SubCommand::with_name("image")
+                .about("Writes images of Owen scrambled Sobol points")
+                .args(&sampler_args())
+                .arg(dimension_pairs_arg(
+                    "Pair of dimensions to plot, instead of 0,1.  Can be given multiple times",
+                ))
+                .arg(resolution_arg(
+                    "320",
+                    "Width and height of each point set, in pixels",
+                ))
+                .arg(points_arg(
+                    "256,1024,4096",
+                    "Comma separated point counts, drawn left-to-right",
+                ))
+                .arg(count_arg())
+                .arg(image_seed_arg())
+                .arg(output_dir_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("grid")
+                .about(
+                    "Writes contact-sheet images of every pair of the first few dimensions \
+                     of Owen scrambled Sobol points",
+                )
+                .args(&sampler_args())
+                .arg(dimensions_arg(None))
+                .arg(resolution_arg(
+                    "128",
+                    "Width and height of each cell, in pixels",
+                ))
+                .arg(points_arg("1024", "Point count"))
+                .arg(count_arg())
+                .arg(image_seed_arg())
+                .arg(output_dir_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("spectrum")
+                .about(
+                    "Writes images of the Fourier power spectrum of 2d Owen scrambled Sobol \
+                     points, averaged over many seeds",
+                )
+                .args(&sampler_args())
+                .arg(dimension_pairs_arg(
+                    "Pair of dimensions to compute the spectrum of, instead of 0,1.  Can be \
+                     given multiple times",
+                ))
+                .arg(resolution_arg(
+                    "128",
+                    "Number of frequencies along each axis, which must be even",
+                ))
+                .arg(points_arg("1024", "Point count"))
+                .arg(seeds_arg("64"))
+                .arg(seed_arg())
+                .arg(output_dir_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("discrepancy")
+                .about(
+                    "Tabulates the discrepancy of unscrambled and Owen scrambled Sobol \
+                     points, for power-of-two point counts",
+                )
+                .args(&hash_args())
+                .arg(direction_numbers_arg())
+                .arg(dimensions_arg(None))
+                .arg(seeds_arg("16"))
+                .arg(seed_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("convergence")
+                .about(
+                    "Measures how the integration error of unscrambled and Owen scrambled \
+                     Sobol points falls off with the point count, on a suite of test functions",
+                )
+                .args(&hash_args())
+                .arg(direction_numbers_arg())
+                .arg(seeds_arg("32"))
+                .arg(seed_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("independence")
+                .about(
+                    "Checks that the per-dimension seeds derived from a point set's seed \
+                     give independent scrambles, for every pair of dimensions",
+                )
+                .args(&sampler_args())
+                .arg(dimensions_arg(Some("8")))
+                .arg(seeds_arg("65536"))
+                .arg(seed_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("nets")
+                .about(
+                    "Checks that Owen scrambled Sobol points are still nets, for power-of-two \
+                     point counts",
+                )
+                .args(&hash_args())
+                .arg(direction_numbers_arg())
+                .arg(
+                    clap::Arg::with_name("dims")
+                        .long("dims")
+                        .takes_value(true)
+                        .value_name("DIMS")
+                        .required(true)
+                        .help("Comma separated dimensions to check"),
+                )
+                .arg(seeds_arg("64"))
+                .arg(seed_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("shuffled-nets")
+                .about(
+                    "Checks that power-of-two prefixes of Owen scrambled and shuffled Sobol \
+                     points are (0,m,2)-nets",
+                )
+                .arg(direction_numbers_arg())
+                .arg(seeds_arg("256"))
+                .arg(seed_arg()),
+        )
+        .subcommand(
+            clap::SubCommand::with_name("check-sobol")
+                .about(
+                    "Checks the Gray-code and batched Sobol evaluation against evaluating \
+                     each point directly, and times them",
+                )
+                .arg(direction_numbers_arg())
+                .arg(points_arg("65536", "Point count")),
         )
         .get_matches();
 
-    // Pick what to do based on command line arguments.
-    if args.is_present("test") {
-        let rounds = args
-            .value_of("number")
-            .unwrap_or("10000000")
-            .parse()
-            .unwrap();
-        do_test(rounds, true);
-    } else if args.is_present("search") {
-        let rounds = args.value_of("number").unwrap_or("10000").parse().unwrap();
-        do_hash_search(rounds, true);
-    } else {
-        let image_resolution = 320;
-        let image_count = args.value_of("number").unwrap_or("4").parse().unwrap();
-        let sample_function = if args.is_present("reference") {
-            |i, d, seed| sobol::sample_owen_reference(i, d, seed)
-        } else {
-            |i, d, seed| sobol::sample_owen_fast(i, d, seed)
+    let (command, args) = args.subcommand();
+    let args = args.unwrap();
+    // The image subcommands have their own 32-bit seeds, see
+    // `image_seeds()`.
+    let seed = optional_value::<u64>(args, "seed").unwrap_or_else(rand::random);
+    let output_dir = args.value_of("output_dir").map(|dir| {
+        let dir = Path::new(dir);
+        std::fs::create_dir_all(dir).unwrap_or_else(|e| {
+            eprintln!("Couldn't create \"{}\": {}", dir.display(), e);
+            std::process::exit(1);
+        });
+        dir
+    });
+    let image_options = stats_image_options(args);
+    let stats_image = output_dir.map(|dir| (dir, &image_options));
+
+    // The modes that work on any word size.
+    if WORD_COMMANDS.contains(&command) {
+        return match word_size(args) {
+            WordSize::Bits32 => run_word_command::<u32>(command, args, seed, stats_image),
+            WordSize::Bits64 => run_word_command::<u64>(command, args, seed, stats_image),
         };
+    }
 
-        for seed in 0..image_count {
-            let filename = if args.is_present("reference") {
-                format!("{:02}_ref.png", seed)
-            } else {
-                format!("{:02}.png", seed)
-            };
-            generate_samples_image(
-                sample_function,
-                image_resolution,
-                &[256, 1024, 4096],
+    // Use other direction numbers for the Sobol sequence, if requested.
+    let table_suffix = if let Some(path) = args.value_of("direction_numbers") {
+        let path = Path::new(path);
+        let table = sobol::SobolTable::load(path, None).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+        sobol::set_table(table).unwrap();
+        format!("_{}", path.file_stem().unwrap().to_string_lossy())
+    } else {
+        String::new()
+    };
+
+    let hash = selected_hash_text(args).map(|text| parse_hash::<u32>(&text));
+    let default_hash = || SelectedHash::Registered(hashes::get(u32::DEFAULT_HASH).unwrap());
+
+    // The sampler for the modes that look at point sets, and the suffix
+    // for the filenames of their images.
+    let reference = args.is_present("reference");
+    let sampler = if reference {
+        Sampler::reference()
+    } else {
+        Sampler::owen(hash.as_ref())
+    };
+    let file_suffix = if reference {
+        "_ref".to_string()
+    } else if let Some(h) = &hash {
+        format!("_{}", h.file_name())
+    } else {
+        String::new()
+    } + &table_suffix;
+    let output_path = |filename: String| output_dir.unwrap().join(filename);
+
+    match command {
+        "render" => {
+            for path in args.values_of("files").unwrap().map(Path::new) {
+                let stats = stats_file::read_stats_file(path).unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                });
+                println!("Stats: {}", path.display());
+                print_stats(&stats);
+                println!("Score:\n{:0.6}", score_stats(&stats));
+                println!();
+                let filename = format!("{}.png", path.file_stem().unwrap().to_string_lossy());
+                write_stats_image(
+                    &stats,
+                    &image_options,
+                    &mut File::create(output_path(filename)).unwrap(),
+                );
+            }
+        }
+        "exhaustive" => {
+            let bits: u32 = value(args, "width");
+            if !(1..=exhaustive::MAX_BITS).contains(&bits) {
+                eprintln!(
+                    "The bit width must be between 1 and {}.",
+                    exhaustive::MAX_BITS
+                );
+                std::process::exit(1);
+            }
+            let stats = do_exhaustive(
+                &hash.unwrap_or_else(default_hash),
+                bits,
+                value(args, "seeds"),
                 seed,
-                &filename,
+                stats_image,
             );
+            save_stats(args, &stats);
+        }
+        "bench" => {
+            let hash = hash.unwrap_or_else(default_hash);
+            let hash_ops = hash.ops().unwrap_or_else(|| {
+                eprintln!(
+                    "Hash \"{}\" can't be expressed as HashOps, so can't be benchmarked.",
+                    hash.name()
+                );
+                std::process::exit(1);
+            });
+            do_bench(hash_ops, value(args, "count"), seed);
+        }
+        "discrepancy" => {
+            let dimensions = dimension_count(args, 1);
+            do_discrepancy(hash.as_ref(), dimensions, value(args, "seeds"), seed);
+        }
+        "convergence" => {
+            let max_dimensions = convergence::TEST_FUNCTIONS
+                .iter()
+                .map(|f| f.dimensions)
+                .max()
+                .unwrap();
+            check_dimensions(0..max_dimensions);
+            do_convergence(hash.as_ref(), value(args, "seeds"), seed);
+        }
+        "independence" => {
+            let dimensions = dimension_count(args, 2);
+            if !do_independence(&sampler, dimensions, value(args, "seeds"), seed) {
+                std::process::exit(1);
+            }
         }
+        "nets" => {
+            let dimensions =
+                parse_dimension_list(args.value_of("dims").unwrap()).unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                });
+            if !do_nets(hash.as_ref(), &dimensions, value(args, "seeds"), seed) {
+                std::process::exit(1);
+            }
+        }
+        "shuffled-nets" => {
+            check_dimensions(0..2);
+            if !do_shuffled_nets(value(args, "seeds"), seed) {
+                std::process::exit(1);
+            }
+        }
+        "check-sobol" => {
+            if !do_check_sobol(single_point_count(args)) {
+                std::process::exit(1);
+            }
+        }
+        "spectrum" => {
+            let dim_pairs: Vec<_> = dimension_pairs(args)
+                .into_iter()
+                .map(|(dims, dims_suffix)| (dims, file_suffix.clone() + &dims_suffix))
+                .collect();
+            let resolution = resolution(args);
+            if resolution % 2 != 0 {
+                eprintln!("The spectrum resolution must be even.");
+                std::process::exit(1);
+            }
+            let point_count = single_point_count(args);
+            if point_count == 0 {
+                eprintln!("The spectrum needs at least one point.");
+                std::process::exit(1);
+            }
+            do_spectrum(
+                &sampler,
+                point_count,
+                &dim_pairs,
+                value(args, "seeds"),
+                resolution,
+                seed,
+                &output_path,
+            );
+        }
+        "grid" => {
+            let dimensions = dimension_count(args, 2);
+            let resolution = resolution(args);
+            let point_count = single_point_count(args);
+            for seed in image_seeds(args) {
+                let filename = format!("{:02}{}_grid_{}.png", seed, file_suffix, dimensions);
+                samples_image::write_samples_grid_image(
+                    |i, d, s| sampler.sample(i, d, s),
+                    resolution,
+                    point_count,
+                    dimensions,
+                    seed,
+                    &mut File::create(output_path(filename)).unwrap(),
+                );
+            }
+        }
+        "image" => {
+            let resolution = resolution(args);
+            let point_counts = point_counts(args);
+            let dim_pairs = dimension_pairs(args);
+
+            for seed in image_seeds(args) {
+                for (dims, dims_suffix) in dim_pairs.iter() {
+                    let filename = format!("{:02}{}{}.png", seed, file_suffix, dims_suffix);
+                    samples_image::write_samples_image(
+                        |i, d, s| sampler.sample(i, d, s),
+                        resolution,
+                        &point_counts,
+                        *dims,
+                        seed,
+                        &mut File::create(output_path(filename)).unwrap(),
+                    );
+                }
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// The subcommands that work on any word size, which take --bits and are
+/// run by `run_word_command()`.
+const WORD_COMMANDS: [&str; 5] = ["list-hashes", "test", "compare", "diff", "search"];
+
+/// The word size of the hashes, from the --bits option.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum WordSize {
+    Bits32,
+    Bits64,
+}
+
+/// Runs the subcommands that work on any word size: listing hashes,
+/// testing, comparing and searching.
+fn run_word_command<W: HashWord>(
+    command: &str,
+    args: &clap::ArgMatches,
+    seed: u64,
+    stats_image: Option<(&Path, &StatsImageOptions)>,
+) {
+    let hash = selected_hash_text(args).map(|text| parse_hash::<W>(&text));
+    let default_hash = || SelectedHash::Registered(hashes::get::<W>(W::DEFAULT_HASH).unwrap());
+
+    match command {
+        "list-hashes" => {
+            for h in W::HASHES.iter() {
+                println!("{}\n    {}\n    {}", h.name, h.description, h.source);
+            }
+        }
+        "test" => {
+            let hash = hash.unwrap_or_else(default_hash);
+            let stats = do_test(&hash, stat_rounds(args, "rounds"), seed, stats_image);
+            save_stats(args, &stats);
+        }
+        "compare" => {
+            let hash_a = parse_hash::<W>(args.value_of("hash_a").unwrap());
+            let hash_b = parse_hash::<W>(args.value_of("hash_b").unwrap());
+            do_compare(&hash_a, &hash_b, stat_rounds(args, "rounds"), seed);
+        }
+        "diff" => {
+            let a = parse_diff_input::<W>(args.value_of("a").unwrap());
+            let b = parse_diff_input::<W>(args.value_of("b").unwrap());
+            do_diff(&a, &b, stat_rounds(args, "rounds"), seed, stats_image);
+        }
+        "search" => {
+            let mut search = if let Some(path) = args.value_of("resume") {
+                let mut search = search::Search::<W>::read_checkpoint(Path::new(path))
+                    .unwrap_or_else(|e| {
+                        eprintln!("Couldn't resume search: {}", e);
+                        std::process::exit(1);
+                    });
+                if let Some(rounds) = optional_value(args, "rounds") {
+                    search.rounds = rounds;
+                }
+                search
+            } else {
+                let rounds = optional_value(args, "rounds").unwrap_or(10000);
+                let template = hash.unwrap_or_else(default_hash);
+                let template_ops = template.ops().unwrap_or_else(|| {
+                    eprintln!(
+                        "Hash \"{}\" can't be expressed as HashOps, so can't be used for searching.",
+                        template.name()
+                    );
+                    std::process::exit(1);
+                });
+                let strategy =
+                    search::Strategy::from_name(args.value_of("strategy").unwrap_or("evolve"))
+                        .unwrap();
+                search::Search::new(strategy, template_ops, rounds, seed)
+            };
+            if args.is_present("stat_rounds") {
+                search.stat_rounds = stat_rounds(args, "stat_rounds");
+            }
+            let checkpoint = args
+                .value_of("checkpoint")
+                .or_else(|| args.value_of("resume"))
+                .map(|path| (Path::new(path), value(args, "checkpoint_every")));
+            do_hash_search(search, checkpoint, stats_image);
+        }
+        _ => unreachable!(),
     }
 }
 
 //=======================================================================
-// SUB-COMMANDS
+// COMMAND LINE
 //=======================================================================
 
-/// Generates a bunch of 2d Owen-scrambled Sobol points, and writes them
-/// to an image.
-fn generate_samples_image<F>(
-    sample: F,
-    resolution: usize,
-    point_counts: &[u32], // A list of point-counts, which will be drawn sequentially in the image, left-to-right.
-    seed: u32,
-    image_path: &str,
-) where
-    F: Fn(u32, u32, u32) -> f32, // (sample_index, dimension, seed) -> coordinate
-{
-    const POINT_RADIUS: usize = 2;
-
-    let width = resolution * point_counts.len();
-    let height = resolution;
-    let mut image = vec![0xffu8; width * height * 4];
-
-    // Draws a point on the image.
-    let mut plot = |x: usize, y: usize| {
-        let min_x = x.saturating_sub(POINT_RADIUS);
-        let min_y = y.saturating_sub(POINT_RADIUS);
-        let max_x = (x + POINT_RADIUS + 1).min(width);
-        let max_y = (y + POINT_RADIUS + 1).min(height);
-
-        for yy in min_y..max_y {
-            for xx in min_x..max_x {
-                let x2 = x as isize - xx as isize;
-                let y2 = y as isize - yy as isize;
-                if (((x2 * x2) + (y2 * y2)) as f64).sqrt() <= POINT_RADIUS as f64 {
-                    image[(yy * width + xx) * 4] = 0x00;
-                    image[(yy * width + xx) * 4 + 1] = 0x00;
-                    image[(yy * width + xx) * 4 + 2] = 0x00;
-                    image[(yy * width + xx) * 4 + 3] = 0xFF;
-                }
+/// The options for selecting a hash.
+fn hash_args() -> [clap::Arg<'static, 'static>; 2] {
+    [
+        clap::Arg::with_name("hash")
+            .long("hash")
+            .takes_value(true)
+            .help(
+                "Scramble hash to use: either the name of a registered hash \
+                 (see list-hashes), or a hash program such as \
+                 \"shladd 2; mulxor 0xfe9b5742; seedmix\"",
+            ),
+        clap::Arg::with_name("hash_file")
+            .long("hash-file")
+            .takes_value(true)
+            .value_name("FILE")
+            .conflicts_with("hash")
+            .help("File containing the scramble hash to use, in the same format as --hash"),
+    ]
+}
+
+/// The options for selecting how to scramble point sets.
+fn sampler_args() -> [clap::Arg<'static, 'static>; 4] {
+    let [hash, hash_file] = hash_args();
+    [
+        hash,
+        hash_file,
+        clap::Arg::with_name("reference")
+            .long("reference")
+            .alias("ref")
+            .conflicts_with_all(&["hash", "hash_file"])
+            .help("Use the reference Owen scramble instead of a hash"),
+        direction_numbers_arg(),
+    ]
+}
+
+fn direction_numbers_arg() -> clap::Arg<'static, 'static> {
+    clap::Arg::with_name("direction_numbers")
+        .long("direction-numbers")
+        .takes_value(true)
+        .value_name("FILE")
+        .help(
+            "Joe-Kuo direction numbers file to use for the Sobol sequence, instead of \
+             the built-in table",
+        )
+}
+
+fn bits_arg() -> clap::Arg<'static, 'static> {
+    clap::Arg::with_name("bits")
+        .long("bits")
+        .takes_value(true)
+        .possible_values(&["32", "64"])
+        .default_value("32")
+        .help("Word size of the hashes")
+}
+
+fn rounds_arg() -> clap::Arg<'static, 'static> {
+    clap::Arg::with_name("rounds")
+        .long("rounds")
+        .takes_value(true)
+        .value_name("N")
+        .default_value("10000000")
+        .help("How many rounds of measurements to take")
+}
+
+fn seed_arg() -> clap::Arg<'static, 'static> {
+    clap::Arg::with_name("seed")
+        .long("seed")
+        .takes_value(true)
+        .value_name("SEED")
+        .help("Master seed, to make results reproducible.  Random if not given")
+}
+
+/// The seed option for the image subcommands, which are reproducible by
+/// default.
+fn image_seed_arg() -> clap::Arg<'static, 'static> {
+    clap::Arg::with_name("seed")
+        .long("seed")
+        .takes_value(true)
+        .value_name("SEED")
+        .default_value("0")
+        .help("Seed of the first image.  Each further image uses the next seed")
+}
+
+fn seeds_arg(default: &'static str) -> clap::Arg<'static, 'static> {
+    clap::Arg::with_name("seeds")
+        .long("seeds")
+        .takes_value(true)
+        .value_name("N")
+        .default_value(default)
+        .help("How many seeds to average over or check")
+}
+
+fn count_arg() -> clap::Arg<'static, 'static> {
+    clap::Arg::with_name("count")
+        .long("count")
+        .takes_value(true)
+        .value_name("N")
+        .default_value("4")
+        .help("How many images to write, each with its own seed")
+}
+
+fn dimensions_arg(default: Option<&'static str>) -> clap::Arg<'static, 'static> {
+    let arg = clap::Arg::with_name("dimensions")
+        .long("dimensions")
+        .takes_value(true)
+        .value_name("N")
+        .help("How many dimensions to use, starting from the first");
+    match default {
+        Some(default) => arg.default_value(default),
+        None => arg.required(true),
+    }
+}
+
+/// The --dims option for subcommands that work on pairs of dimensions.
+fn dimension_pairs_arg(help: &'static str) -> clap::Arg<'static, 'static> {
+    clap::Arg::with_name("dims")
+        .long("dims")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .use_delimiter(false)
+        .value_name("X,Y")
+        .help(help)
+}
+
+fn resolution_arg(default: &'static str, help: &'static str) -> clap::Arg<'static, 'static> {
+    clap::Arg::with_name("resolution")
+        .long("resolution")
+        .takes_value(true)
+        .value_name("N")
+        .default_value(default)
+        .help(help)
+}
+
+fn points_arg(default: &'static str, help: &'static str) -> clap::Arg<'static, 'static> {
+    clap::Arg::with_name("points")
+        .long("points")
+        .takes_value(true)
+        .value_name("N")
+        .default_value(default)
+        .help(help)
+}
+
+/// The options for how to draw stats images.
+fn stats_image_args() -> [clap::Arg<'static, 'static>; 2] {
+    [
+        clap::Arg::with_name("image_mode")
+            .long("image-mode")
+            .takes_value(true)
+            .possible_values(StatsImageMode::NAMES)
+            .default_value("values")
+            .help(
+                "What stats images show: the values, or their deviation from those of a \
+                 proper Owen scramble",
+            ),
+        clap::Arg::with_name("log_scale")
+            .long("log-scale")
+            .help("Scale the colours of stats images logarithmically"),
+    ]
+}
+
+/// The stats image options given on the command line.
+fn stats_image_options(args: &clap::ArgMatches) -> StatsImageOptions {
+    StatsImageOptions {
+        mode: args
+            .value_of("image_mode")
+            .and_then(StatsImageMode::from_name)
+            .unwrap_or(StatsImageMode::Values),
+        log_scale: args.is_present("log_scale"),
+    }
+}
+
+fn save_arg() -> clap::Arg<'static, 'static> {
+    clap::Arg::with_name("save")
+        .long("save")
+        .takes_value(true)
+        .value_name("FILE")
+        .multiple(true)
+        .number_of_values(1)
+        .help(
+            "File to save the stats to, as JSON or CSV depending on its extension.  Can be \
+             given multiple times",
+        )
+}
+
+fn output_dir_arg() -> clap::Arg<'static, 'static> {
+    clap::Arg::with_name("output_dir")
+        .long("output-dir")
+        .takes_value(true)
+        .value_name("DIR")
+        .default_value(".")
+        .help("Directory to write images to")
+}
+
+/// Parses the value of a command line option that has a default or is
+/// required, exiting with an error message if it's invalid.
+fn value<T: std::str::FromStr>(args: &clap::ArgMatches, name: &str) -> T {
+    optional_value(args, name).unwrap()
+}
+
+/// Same as `value()`, but for options that may not be given.
+fn optional_value<T: std::str::FromStr>(args: &clap::ArgMatches, name: &str) -> Option<T> {
+    args.value_of(name).map(|text| {
+        text.parse().unwrap_or_else(|_| {
+            eprintln!(
+                "Invalid value \"{}\" for --{}.",
+                text,
+                name.replace('_', "-")
+            );
+            std::process::exit(1);
+        })
+    })
+}
+
+/// The comma separated point counts of the --points option, which must
+/// each be at least 1.
+fn point_counts(args: &clap::ArgMatches) -> Vec<u32> {
+    let text = args.value_of("points").unwrap();
+    text.split(',')
+        .map(|part| match part.trim().parse() {
+            Ok(n) if n > 0 => n,
+            _ => {
+                eprintln!("Invalid point count \"{}\" in \"{}\".", part, text);
+                std::process::exit(1);
             }
+        })
+        .collect()
+}
+
+/// The seeds of the images to write: --count seeds starting from --seed.
+fn image_seeds(args: &clap::ArgMatches) -> impl Iterator<Item = u32> {
+    let first: u32 = value(args, "seed");
+    let count: u32 = value(args, "count");
+    if count > 0 && first.checked_add(count - 1).is_none() {
+        eprintln!("The image seeds must fit in 32 bits, so --seed plus --count is too large.");
+        std::process::exit(1);
+    }
+    (0..count).map(move |k| first + k)
+}
+
+/// The point count of the --points option, for subcommands that take just
+/// one.
+fn single_point_count(args: &clap::ArgMatches) -> u32 {
+    match point_counts(args)[..] {
+        [n] => n,
+        _ => {
+            eprintln!("Only one point count can be given.");
+            std::process::exit(1);
         }
-    };
+    }
+}
+
+/// The --bits option.
+fn word_size(args: &clap::ArgMatches) -> WordSize {
+    match value::<u32>(args, "bits") {
+        32 => WordSize::Bits32,
+        64 => WordSize::Bits64,
+        _ => unreachable!(), // Checked by clap.
+    }
+}
+
+/// The --resolution option, which must be at least 1.
+fn resolution(args: &clap::ArgMatches) -> usize {
+    let resolution = value(args, "resolution");
+    if resolution == 0 {
+        eprintln!("The resolution must be at least 1.");
+        std::process::exit(1);
+    }
+    resolution
+}
+
+/// A number of rounds to measure stats with, which must be at least 1.
+fn stat_rounds(args: &clap::ArgMatches, name: &str) -> u32 {
+    let rounds = value(args, name);
+    if rounds == 0 {
+        eprintln!("The number of rounds must be at least 1.");
+        std::process::exit(1);
+    }
+    rounds
+}
+
+/// The --dimensions option, checked against the Sobol table.
+fn dimension_count(args: &clap::ArgMatches, min: u32) -> u32 {
+    let dimensions = value(args, "dimensions");
+    let max_dimension = sobol::table().dimensions();
+    if !(min..=max_dimension).contains(&dimensions) {
+        eprintln!(
+            "The dimension count must be between {} and {}.",
+            min, max_dimension
+        );
+        std::process::exit(1);
+    }
+    dimensions
+}
+
+/// The dimension pairs of the --dims option, each with the suffix for the
+/// names of its files.  That's just 0,1 with no suffix if none were given.
+fn dimension_pairs(args: &clap::ArgMatches) -> Vec<((u32, u32), String)> {
+    match args.values_of("dims") {
+        Some(values) => values
+            .map(|text| {
+                let (x, y) = parse_dimension_pair(text).unwrap_or_else(|e| {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                });
+                ((x, y), format!("_{}_{}", x, y))
+            })
+            .collect(),
+        None => {
+            check_dimensions(0..2);
+            vec![((0, 1), String::new())]
+        }
+    }
+}
+
+/// Exits with an error message if any of `dimensions` isn't in the Sobol
+/// table, rather than letting the sampling panic on it later.
+fn check_dimensions(dimensions: std::ops::Range<u32>) {
+    if let Some(last) = dimensions.last() {
+        sobol::table().check_dimension(last).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+    }
+}
+
+/// Saves stats to the files given with --save, if any.
+fn save_stats(args: &clap::ArgMatches, stats: &Stats) {
+    for path in args.values_of("save").into_iter().flatten() {
+        stats_file::write_stats_file(stats, Path::new(path)).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        });
+    }
+}
+
+/// The text of the hash selected with --hash or --hash-file, if any.
+fn selected_hash_text(args: &clap::ArgMatches) -> Option<String> {
+    if let Some(path) = args.value_of("hash_file") {
+        Some(std::fs::read_to_string(path).unwrap_or_else(|e| {
+            eprintln!("Couldn't read hash file \"{}\": {}", path, e);
+            std::process::exit(1);
+        }))
+    } else {
+        args.value_of("hash").map(|text| text.to_string())
+    }
+}
 
-    // Plot the points at the various point counts.
-    for (set_idx, &point_count) in point_counts.iter().enumerate() {
-        for i in 0..point_count {
-            let x = sample(i, 0, seed);
-            let y = sample(i, 1, seed + 1);
-            plot(
-                (x * (resolution - 1) as f32) as usize + (resolution * set_idx),
-                (y * (resolution - 1) as f32) as usize,
+/// Parses a dimension pair given on the command line, as "X,Y".
+fn parse_dimension_pair(text: &str) -> Result<(u32, u32), String> {
+    match parse_dimension_list(text)?[..] {
+        [x, y] => Ok((x, y)),
+        _ => Err(format!(
+            "Invalid dimension pair \"{}\", expected \"X,Y\".",
+            text
+        )),
+    }
+}
+
+/// Parses a comma separated list of dimensions given on the command line.
+fn parse_dimension_list(text: &str) -> Result<Vec<u32>, String> {
+    text.split(',')
+        .map(|part| {
+            let dim: u32 = part
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid dimension \"{}\" in \"{}\".", part, text))?;
+            sobol::table().check_dimension(dim)?;
+            Ok(dim)
+        })
+        .collect()
+}
+
+/// Parses a hash given on the command line, exiting with an error
+/// message if it's invalid.
+fn parse_hash<W: HashWord>(text: &str) -> SelectedHash<W> {
+    SelectedHash::from_text(text).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    })
+}
+
+/// Parses a side of a diff given on the command line, along with a
+/// description to print it by.  Paths ending in .json or .csv are saved
+/// stats, other existing files contain a hash, and anything else is a hash
+/// in the same format as --hash.
+fn parse_diff_input<W: HashWord>(text: &str) -> (DiffInput<W>, String) {
+    let path = Path::new(text);
+    let hash = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") | Some("csv") => {
+            let stats = stats_file::read_stats_file(path).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            });
+            return (
+                DiffInput::Stats(Box::new(stats)),
+                format!("saved stats \"{}\"", text),
             );
         }
+        _ if path.is_file() => {
+            let hash_text = std::fs::read_to_string(path).unwrap_or_else(|e| {
+                eprintln!("Couldn't read hash file \"{}\": {}", text, e);
+                std::process::exit(1);
+            });
+            parse_hash(&hash_text)
+        }
+        _ => parse_hash(text),
+    };
+    let description = format!("\"{}\": {}", hash.name(), hash.description());
+    (DiffInput::Hash(hash), description)
+}
+
+//=======================================================================
+// SUB-COMMANDS
+//=======================================================================
+
+/// Computes the power spectrum of 2d Owen-scrambled Sobol points for each
+/// of the given pairs of dimensions, averaged over `seed_count` seeds, and
+/// writes it to images: the periodogram to "spectrum{suffix}.png" and the
+/// radially averaged spectrum to "spectrum_radial{suffix}.png", where the
+/// suffix is the one given with the pair.  `output_path` maps file names
+/// to paths.
+fn do_spectrum(
+    sampler: &Sampler,
+    point_count: u32,
+    dim_pairs: &[((u32, u32), String)],
+    seed_count: usize,
+    resolution: usize,
+    seed: u64,
+    output_path: &dyn Fn(String) -> PathBuf,
+) {
+    println!("Points: {}", point_count);
+    println!("Seed: {}", seed);
+
+    for (dims, file_suffix) in dim_pairs.iter() {
+        println!("Dimensions: {},{}", dims.0, dims.1);
+        let spectrum = experiments::point_set_spectrum(
+            sampler,
+            point_count,
+            *dims,
+            seed_count,
+            resolution,
+            seed,
+        );
+        let radial = spectrum.radial_average();
+
+        spectrum.write_image(
+            &mut File::create(output_path(format!("spectrum{}.png", file_suffix))).unwrap(),
+        );
+        spectrum::write_radial_image(
+            &radial,
+            &mut File::create(output_path(format!("spectrum_radial{}.png", file_suffix))).unwrap(),
+        );
     }
+}
 
-    let mut file = File::create(image_path).unwrap();
-    png_encode_mini::write_rgba_from_u8(&mut file, &image, width as u32, height as u32);
-}
-
-/// Tests the statistics of a hash, and prints the results to the console.
-/// Optionally writes a png image as well.
-fn do_test(rounds: u32, with_image: bool) {
-    let stats = measure_stats(
-        |n, seed| {
-            let mut n = n;
-            // // Reference Owen scramble implementation, performed on
-            // // reversed bits.
-            // n = n.reverse_bits();
-            // n = sobol::owen_scramble_reference_u32(n, seed);
-            // n = n.reverse_bits();
-
-            // // Original Laine-Karras hash.
-            // n = n.wrapping_add(seed);
-            // n ^= n.wrapping_mul(0x6c50b47c);
-            // n ^= n.wrapping_mul(0xb82f1e52);
-            // n ^= n.wrapping_mul(0xc7afe638);
-            // n ^= n.wrapping_mul(0x8d22f6e6);
-
-            // // Improved version 2.
-            // // From https://psychopath.io/post/2021_01_02_sobol_sampling_take_2
-            // n = n.wrapping_add(seed);
-            // n ^= 0xdc967795;
-            // n = n.wrapping_mul(0x97b754b7);
-            // n ^= 0x866350b1;
-            // n = n.wrapping_mul(0x9e3779cd);
-
-            // // From https://psychopath.io/post/2021_01_30_building_a_better_lk_hash
-            // // Old broken version.
-            // n *= 0x788aeeed;
-            // n ^= n * 0x41506a02;
-            // n += seed;
-            // n *= seed | 1;
-            // n ^= n * 0x7483dc64;
-
-            // // From https://psychopath.io/post/2021_01_30_building_a_better_lk_hash
-            // // Old fast broken version.
-            // n += n << 2;
-            // n ^= n * 0xfe9b5742;
-            // n += seed;
-            // n *= seed | 1;
-
-            // From https://psychopath.io/post/2021_01_30_building_a_better_lk_hash
-            // From the updated post, fixing the issue that Matt Pharr found.
-            n ^= n * 0x3d20adea;
-            n += seed;
-            n *= (seed >> 16) | 1;
-            n ^= n * 0x05526c56;
-            n ^= n * 0x53a22864;
-
-            // // Run a generated hash.  Note: a constant of zero in an op
-            // // indicates using the seed.
-            // n = exec_hash_slice(
-            //     &[HashOp::ShlAdd(2), HashOp::MulXor(2924493090), HashOp::SeedMix, ],
-            //     n,
-            //     seed,
-            // );
-
-            // // High quality reference.
-            // n = sobol::owen_scramble_reference_u32(n, seed);
-
-            n
-        },
-        rounds,
-        true,
-    );
+/// Formats a standard error to follow a value, or a note that it's
+/// unavailable.
+fn format_std_error(error: Option<f64>) -> String {
+    match error {
+        Some(error) => format!(" ± {:0.6}", error),
+        None => " (error unavailable)".to_string(),
+    }
+}
+
+/// Tests the statistics of a hash with `experiments::test_hash()`, and
+/// prints the results to the console.  Optionally writes a png image to
+/// "stats.png" in the given directory as well.
+/// Returns the stats.
+fn do_test<W: HashWord>(
+    hash: &SelectedHash<W>,
+    rounds: u32,
+    seed: u64,
+    image: Option<(&Path, &StatsImageOptions)>,
+) -> Stats {
+    println!("Testing hash \"{}\": {}", hash.name(), hash.description());
+    println!("Seed: {}", seed);
+    let test = experiments::test_hash(hash, rounds, seed, true);
+    let stats = test.estimate.stats;
 
     // Print stats.
-    print_stats(stats);
+    print_stats_estimate(&test.estimate);
+    println!(
+        "Score:\n{:0.6}{}",
+        test.score,
+        format_std_error(test.score_error)
+    );
     println!();
 
     // Write avalanche image.
-    if with_image {
-        write_stats_image(stats, &mut File::create("stats.png").unwrap());
+    if let Some((dir, options)) = image {
+        write_stats_image(
+            &stats,
+            options,
+            &mut File::create(dir.join("stats.png")).unwrap(),
+        );
     }
+
+    stats
 }
 
-/// Randomly searches for better hashes, and prints the result to console.
-/// Optionally also saves statistics png images of the top produced hashes.
-///
-/// All this does is generate hashes randomly, and keep the highest-scoring
-/// ones.  No fancy mutation approaches or whatnot, unfortunately.
-fn do_hash_search(rounds: usize, with_image: bool) {
-    use std::collections::HashMap;
-
-    const CANDIDATE_COUNT: usize = 4;
-    const STAT_ROUNDS: u32 = 1 << 22;
-
-    // Method to use to generate new hashes.
-    let generate = || {
-        // // Generate a totally random 5-op hash.
-        // [
-        //     HashOp::gen_random(),
-        //     HashOp::gen_random(),
-        //     HashOp::gen_random(),
-        //     HashOp::gen_random(),
-        // ]
-
-        // Start with an existing hash, and generate a new random
-        // constant for some of the operations.
-        [
-            HashOp::MulXor(123).new_constant(),
-            HashOp::SeedMix,
-            HashOp::MulXor(123).new_constant(),
-            HashOp::MulXor(123).new_constant(),
-        ]
+/// Measures the statistics of two hashes with
+/// `experiments::compare_hashes()`, and prints whether one of them scores
+/// significantly better than the other.
+fn do_compare<W: HashWord>(
+    hash_a: &SelectedHash<W>,
+    hash_b: &SelectedHash<W>,
+    rounds: u32,
+    seed: u64,
+) {
+    println!("A: \"{}\": {}", hash_a.name(), hash_a.description());
+    println!("B: \"{}\": {}", hash_b.name(), hash_b.description());
+    println!("Seed: {}", seed);
+    let comparison = experiments::compare_hashes(hash_a, hash_b, rounds, seed, true);
+
+    println!(
+        "Score A:            {:0.6}{}",
+        comparison.a.score,
+        format_std_error(comparison.a.score_error)
+    );
+    println!(
+        "Score B:            {:0.6}{}",
+        comparison.b.score,
+        format_std_error(comparison.b.score_error)
+    );
+    println!(
+        "Difference (B - A): {:0.6}{}",
+        comparison.difference,
+        format_std_error(comparison.difference_error)
+    );
+    let (z, p) = match comparison.significance() {
+        Some(significance) => significance,
+        None => {
+            println!(
+                "Can't test for a significant difference: needs at least {} rounds.",
+                MIN_ERROR_ROUNDS
+            );
+            return;
+        }
     };
+    println!("z = {:0.2}, p = {:0.4}", z, p);
+    if p < 0.05 {
+        println!(
+            "{} is significantly better (lower score) at the 5% level.",
+            if comparison.difference < 0.0 {
+                "B"
+            } else {
+                "A"
+            }
+        );
+    } else {
+        println!("No significant difference at the 5% level.");
+    }
+}
 
-    //----------------
-    // Do actual optimization process.
-    //----------------
+/// Compares the stats of two hashes, or of saved stats, with
+/// `experiments::diff_hashes()`, and prints how much each output bit
+/// contributes to their scores, and whether it got better or worse from A
+/// to B.  Optionally writes an image of both stats and their difference to
+/// "diff.png" in the given directory as well.
+///
+/// Each side is given along with a description to print it by.  If both
+/// sides are hashes the differences are tested for significance at the 5%
+/// level.  Saved stats have no error estimates, so then every difference
+/// counts.
+fn do_diff<W: HashWord>(
+    a: &(DiffInput<W>, String),
+    b: &(DiffInput<W>, String),
+    rounds: u32,
+    seed: u64,
+    image: Option<(&Path, &StatsImageOptions)>,
+) {
+    println!("A: {}", a.1);
+    println!("B: {}", b.1);
+    if let (DiffInput::Hash(_), _) | (_, DiffInput::Hash(_)) = (&a.0, &b.0) {
+        println!("Seed: {}", seed);
+    }
+    let hash_diff = experiments::diff_hashes(&a.0, &b.0, rounds, seed, true).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    });
+    let diff = &hash_diff.diff;
 
-    let mut candidates: Vec<_> = (0..CANDIDATE_COUNT)
-        .map(|_| (generate(), std::f64::INFINITY, STATS_ZERO))
-        .collect();
-    let last_idx = candidates.len() - 1;
+    println!("Score A:            {:0.6}", diff.score_a);
+    println!("Score B:            {:0.6}", diff.score_b);
+    println!(
+        "Difference (B - A): {:0.6}{}",
+        diff.score_b - diff.score_a,
+        format_std_error(diff.score_error)
+    );
 
+    println!("\nPer-output-bit score (lower is better):");
+    println!("{:>3}  {:>10}  {:>10}  {:>10}", "bit", "A", "B", "B - A");
+    for bit in diff.bits.iter() {
+        let verdict = match bit.verdict {
+            Verdict::Same => "",
+            Verdict::Better => "better",
+            Verdict::Worse => "worse",
+        };
+        let line = format!(
+            "{:>3}  {:>10.6}  {:>10.6}  {:>10.6}{:<20}  {}",
+            bit.bit,
+            bit.score_a,
+            bit.score_b,
+            bit.difference(),
+            format_std_error(bit.error),
+            verdict
+        );
+        println!("{}", line.trim_end());
+    }
+    let better = diff.bits_with(Verdict::Better);
+    let worse = diff.bits_with(Verdict::Worse);
+    let same = diff.bits_with(Verdict::Same);
     println!();
-    for round in 0..rounds {
-        print!("\rround {}/{}", round, rounds);
-        std::io::stdout().flush();
-
-        // Generate and score a new hash.
-        let new_hash = generate();
-        let (stats, score) = {
-            let stats = measure_stats(
-                |n, seed| exec_hash_slice(&new_hash[..], n, seed),
-                STAT_ROUNDS,
-                false,
+    println!("Better in B: {} output bits {:?}", better.len(), better);
+    println!("Worse in B:  {} output bits {:?}", worse.len(), worse);
+    if diff.has_std_error {
+        println!("No significant difference: {} output bits", same.len());
+    } else {
+        println!("Same: {} output bits", same.len());
+        if hash_diff.measured {
+            println!(
+                "(Too few rounds for error estimates, which need at least {}, so every difference counts.)",
+                MIN_ERROR_ROUNDS
             );
-            (stats, score_stats(&stats))
+        } else {
+            println!("(Saved stats have no error estimates, so every difference counts.)");
+        }
+    }
+    println!();
+
+    if let Some((dir, options)) = image {
+        write_stats_diff_image(
+            &hash_diff.stats_a,
+            &hash_diff.stats_b,
+            options,
+            &mut File::create(dir.join("diff.png")).unwrap(),
+        );
+    }
+}
+
+/// Checks that `sobol::dimension_seed()` gives independent scrambles with
+/// `experiments::check_independence()`, and prints the results.  With the
+/// default hash this is checking `sobol::sample_owen()`.  Returns whether
+/// the check passed.
+fn do_independence(sampler: &Sampler, dimensions: u32, seed_count: usize, seed: u64) -> bool {
+    use independence::{PairDeviation, INDICES, MAX_DEVIATION};
+
+    println!("Dimensions: {}", dimensions);
+    println!("Seeds: {}", seed_count);
+    println!("Seed: {}", seed);
+    let check = experiments::check_independence(sampler, dimensions, seed_count, seed);
+
+    println!();
+    println!(
+        "Largest deviation from independence, in standard deviations (limit {}):",
+        MAX_DEVIATION
+    );
+    println!(
+        "{:>8}  {:>16}  {:>16}",
+        "index", "derived seeds", "shared seed"
+    );
+    for ((index, d), s) in INDICES
+        .iter()
+        .zip(check.derived.iter())
+        .zip(check.shared.iter())
+    {
+        let format = |d: &PairDeviation| {
+            format!(
+                "{:8.2} ({:>2},{:>2})",
+                d.deviation, d.dimensions.0, d.dimensions.1
+            )
         };
+        println!("{:>8}  {:>16}  {:>16}", index, format(d), format(s));
+    }
 
-        // If it beats the current lowest-scoring hash, replace it.
-        if score < candidates[last_idx].1 {
-            candidates[last_idx] = (new_hash, score, stats);
-            candidates.sort_unstable_by(|x, y| x.1.partial_cmp(&y.1).unwrap());
+    let passed = check.passed();
+    println!();
+    if passed {
+        println!("PASSED: the derived dimension seeds give independent scrambles.");
+    } else {
+        println!("FAILED: the derived dimension seeds give correlated scrambles.");
+    }
+    passed
+}
+
+/// Checks that Owen scrambling preserves the net properties of the Sobol
+/// sequence in the given dimensions with `experiments::check_nets()`, and
+/// prints the results.  Returns whether the check passed.
+fn do_nets(hash: Option<&SelectedHash>, dimensions: &[u32], seed_count: usize, seed: u64) -> bool {
+    let dimension_list: Vec<String> = dimensions.iter().map(|d| d.to_string()).collect();
+    println!("Dimensions: {}", dimension_list.join(","));
+    println!("Seeds: {}", seed_count);
+    println!("Seed: {}", seed);
+    let check = experiments::check_nets(hash, dimensions, seed_count, seed);
+
+    println!();
+    println!("Seeds for which the points aren't a (t,m,s)-net:");
+    print!("{:>8}  {:>4}", "points", "t");
+    for (sampler, _) in check.results.iter() {
+        print!("  {:>10}", sampler.name);
+    }
+    println!();
+    for (m, t) in check.t.iter().enumerate() {
+        print!("{:>8}  {:>4}", 1 << m, t);
+        for (_, failures) in check.results.iter() {
+            print!("  {:>10}", failures[m].seeds);
         }
+        println!();
     }
+
+    for (sampler, failures) in check.results.iter() {
+        if let Some((m, violation)) = failures
+            .iter()
+            .enumerate()
+            .find_map(|(m, f)| f.first.as_ref().map(|v| (m, v)))
+        {
+            let shape: Vec<String> = violation
+                .shape
+                .iter()
+                .map(|k| (1 << k).to_string())
+                .collect();
+            println!();
+            println!(
+                "First violation for {}: {} points, {} of the cells of the {} grid \
+                 don't have {} points.",
+                sampler.name,
+                1 << m,
+                violation.bad_cells,
+                shape.join("x"),
+                1 << check.t[m]
+            );
+        }
+    }
+
+    let passed = check.passed();
     println!();
+    if passed {
+        println!("PASSED: the scrambled points are nets.");
+    } else {
+        println!("FAILED: some of the scrambled points aren't nets.");
+    }
+    passed
+}
 
-    // Print out the top hashes, and (optionally) write statistics png images
-    // for them as well.
-    for (i, c) in candidates.iter().enumerate() {
-        println!("Score: {}", c.1);
+/// Checks that every power-of-two prefix of `sobol::sample_owen_shuffled()`
+/// is a (0,m,2)-net with `experiments::check_shuffled_nets()`, and prints
+/// the results.  Returns whether the check passed.
+fn do_shuffled_nets(seed_count: usize, seed: u64) -> bool {
+    println!("Seeds: {}", seed_count);
+    println!("Seed: {}", seed);
+    let check = experiments::check_shuffled_nets(seed_count, seed);
+
+    println!();
+    println!("Seeds for which the prefix isn't a (0,m,2)-net:");
+    println!(
+        "{:>8}  {:>10}  {:>22}",
+        "points", "shuffled", "shuffled per dimension"
+    );
+    for (m, (shuffled, control)) in check.shuffled.iter().zip(check.control.iter()).enumerate() {
+        println!("{:>8}  {:>10}  {:>22}", 1 << m, shuffled, control);
+    }
+
+    let passed = check.passed();
+    println!();
+    if passed {
+        println!("PASSED: every prefix of the shuffled points is a (0,m,2)-net.");
+    } else {
+        println!("FAILED: some prefixes of the shuffled points aren't (0,m,2)-nets.");
+    }
+    passed
+}
 
-        print!("&[");
-        for p in c.0.iter() {
-            print!("HashOp::{:?}, ", *p);
+/// Tabulates the discrepancies from `experiments::discrepancy_table()`.
+fn do_discrepancy(hash: Option<&SelectedHash>, dimensions: u32, seed_count: usize, seed: u64) {
+    use discrepancy::Discrepancies;
+
+    println!("Dimensions: {}", dimensions);
+    println!("Seed: {}", seed);
+
+    for table in experiments::discrepancy_table(hash, dimensions, seed_count, seed).iter() {
+        println!("\n{} ({} seeds):", table.sampler.name, table.seed_count);
+        print!("{:>8}", "N");
+        for metric in Discrepancies::NAMES.iter() {
+            print!("{:>14}", metric);
         }
-        println!("]");
-        print_stats(c.2);
         println!();
+        for (log2_n, d) in table.discrepancies.iter().enumerate() {
+            print!("{:>8}", 1 << log2_n);
+            for v in d.values().iter() {
+                print!("{:>14.4e}", v);
+            }
+            println!();
+        }
+    }
+}
 
-        if with_image {
-            write_stats_image(
-                c.2,
-                &mut File::create(&format!("candidate_{:02}.png", i + 1)).unwrap(),
-            );
+/// Tabulates the RMS integration errors vs. point count from
+/// `experiments::convergence_table()`.
+fn do_convergence(hash: Option<&SelectedHash>, seed_count: usize, seed: u64) {
+    println!("Seed: {}", seed);
+    let table = experiments::convergence_table(hash, seed_count, seed);
+
+    for (function, results) in table.functions.iter() {
+        println!(
+            "\n{} ({}D): {}",
+            function.name, function.dimensions, function.description
+        );
+
+        print!("{:>8}", "N");
+        for sampler in table.samplers.iter() {
+            print!("{:>14}", sampler.name);
+        }
+        println!();
+        for log2_n in 0..results[0].rmse.len() {
+            print!("{:>8}", 1u32 << log2_n);
+            for r in results.iter() {
+                print!("{:>14.4e}", r.rmse[log2_n]);
+            }
+            println!();
+        }
+        print!("{:>8}", "slope");
+        for r in results.iter() {
+            match r.slope {
+                Some(slope) => print!("{:>14.3}", slope),
+                None => print!("{:>14}", "-"),
+            }
         }
+        println!();
     }
 }
 
-//=======================================================================
-// UTILS
-//=======================================================================
+/// Computes the exact stats of a `bits`-bit version of a hash with
+/// `experiments::check_exhaustive()`, and prints them along with how they
+/// compare to the sampled stats.  Optionally writes a png image of the
+/// exact stats to "exhaustive.png" in the given directory as well.
+/// Returns the exact stats.
+fn do_exhaustive(
+    hash: &SelectedHash,
+    bits: u32,
+    seed_count: usize,
+    seed: u64,
+    image: Option<(&Path, &StatsImageOptions)>,
+) -> Stats {
+    println!("Testing hash \"{}\": {}", hash.name(), hash.description());
+    println!("Bits: {}", bits);
+    println!("Seed: {}", seed);
+    let check = experiments::check_exhaustive(hash, bits, seed_count, seed, true);
+
+    println!("\nExact:");
+    print_stats(&check.exact);
+    println!("Score:\n{:0.6}", score_stats(&check.exact));
+
+    println!(
+        "\nSampled ({} rounds):",
+        experiments::EXHAUSTIVE_SAMPLED_ROUNDS
+    );
+    print_stats(&check.sampled.stats.truncated(bits as usize));
+    println!(
+        "Score:\n{:0.6}{}",
+        score_stats_bits(&check.sampled.stats, bits as usize),
+        format_std_error(
+            check
+                .sampled
+                .std_error_of(|s| score_stats_bits(s, bits as usize))
+        )
+    );
+
+    println!("\nSampled vs. exact:");
+    println!("Avalanche max |z|: {:0.2}", check.error.avalanche_max_z);
+    println!(
+        "Average bias mean difference: {:0.5}",
+        check.error.avg_bias_mean_difference
+    );
+    println!();
+
+    if let Some((dir, options)) = image {
+        write_stats_image(
+            &check.exact,
+            options,
+            &mut File::create(dir.join("exhaustive.png")).unwrap(),
+        );
+    }
 
-fn hash_u32(n: u32, seed: u32) -> u32 {
-    // Seeding.
-    let mut n = 0x6217c6e1 ^ n.wrapping_add(seed.wrapping_mul(0x9e3779b9));
+    check.exact
+}
+
+/// Checks that `sobol::fill_u32()` and `sobol::gray_code_iter()` give the
+/// same points as `sobol::sobol_u32()`, and times all three, with
+/// `bench::check_sobol()`.  Returns whether the check passed.
+fn do_check_sobol(point_count: u32) -> bool {
+    println!("Dimensions: {}", sobol::table().dimensions());
+    println!("Points: {}", point_count);
+
+    let check = bench::check_sobol(point_count);
+    println!();
+    println!("{:<10} {:>8.3} ns/coordinate", "direct", check.direct_ns);
+    println!(
+        "{:<10} {:>8.3} ns/coordinate  {} mismatches",
+        "batched", check.batched_ns, check.batched_mismatches
+    );
+    println!(
+        "{:<10} {:>8.3} ns/coordinate  {} mismatches",
+        "gray-code", check.gray_code_ns, check.gray_code_mismatches
+    );
 
-    // From https://github.com/skeeto/hash-prospector
-    n ^= n >> 17;
-    n = n.wrapping_mul(0xed5ad4bb);
-    n ^= n >> 11;
-    n = n.wrapping_mul(0xac4c1b51);
-    n ^= n >> 15;
-    n = n.wrapping_mul(0x31848bab);
-    n ^= n >> 14;
+    let passed = check.passed();
+    println!();
+    if passed {
+        println!("PASSED: the batched and Gray-code points match.");
+    } else {
+        println!("FAILED: the batched or Gray-code points don't match.");
+    }
+    passed
+}
 
-    n
+/// Times the ways of running a hash with `bench::bench_hash()`: the
+/// interpreted and compiled hashes on single blocks of inputs, and the
+/// ways `measure_stats()` could run it on its batches.
+fn do_bench(hash_ops: &[HashOp], count: usize, seed: u64) {
+    use bench::{Timing, BATCHES_PER_SEED, BATCH_SIZE};
+
+    println!("Hash: {}", format_hash_ops(hash_ops));
+    println!(
+        "Compiled (seed 0): {:?}",
+        compiled_hash::CompiledHash::new(hash_ops, 0).ops()
+    );
+
+    let print_timings = |timings: &[Timing]| {
+        for t in timings.iter() {
+            println!(
+                "{:<16} {:>8.3} ns/hash{}",
+                t.name,
+                t.ns,
+                if t.matches { "" } else { "  MISMATCH" }
+            );
+        }
+        for t in timings[1..].iter() {
+            println!("{:<16} speedup {:>6.2}x", t.name, timings[0].ns / t.ns);
+        }
+    };
+
+    let bench = bench::bench_hash(hash_ops, count, seed);
+    print_timings(&bench.blocks);
+    println!();
+    println!(
+        "Batches of {}, {} per seed, like measure_stats():",
+        BATCH_SIZE, BATCHES_PER_SEED
+    );
+    print_timings(&bench.batches);
 }
 
-/// Scores the given hash statistics.  Used for searching for better hashes.
+/// Runs a hash search with `experiments::run_search()`, printing its
+/// progress, and prints the result to console.  Optionally also saves
+/// statistics png images of the top produced hashes to the given
+/// directory.
 ///
-/// Lower score is better (like golf!).
-fn score_stats(stats: &Stats) -> f64 {
-    let mut score = 0.0;
-
-    // Avalanche metric.
-    for bit_out in 0..32 {
-        for bit_in in 0..bit_out {
-            let diff = stats.avalanche[bit_in][bit_out] - 0.5;
-            score += diff * diff;
-        }
-    }
-
-    // Avalanche bias metric, trying to match the expected bias of a
-    // proper full Owen scramble.  The first sixteen values here were computed
-    // analytically, and the remaining were approximated following a strong
-    // trend in the values by that point, and should be "reasonably" accurate.
-    const TARGET_BIAS: [f64; 32] = [
-        0.0, 1.0, 0.5, 0.375, 0.273437, 0.19638, 0.139949, 0.099346, 0.070386, 0.049819, 0.035244,
-        0.024927, 0.017628, 0.012466, 0.008815, 0.006233, 0.004407, 0.003117, 0.002204, 0.001558,
-        0.001102, 0.000779, 0.000551, 0.000390, 0.000275, 0.000195, 0.000138, 0.000097, 0.000069,
-        0.000049, 0.000034, 0.000024,
-    ];
-    for bit_out in 0..32 {
-        for bit_in in 0..bit_out {
-            let diff = stats.avalanche_avg_bias[bit_in][bit_out] - TARGET_BIAS[bit_out];
-            score += diff * diff;
-        }
-    }
-
-    // // Tree bias metric
-    // // With the new seed mixing op, this is unnecessary to target since
-    // // it's pretty much always perfect.
-    // for x in 0..32 {
-    //     for y in (x + 1)..32 {
-    //         let diff = (stats.tree_bias[x][y] - 0.5) * 2.0;
-    //         score += diff * diff;
-    //     }
-    // }
-
-    score
+/// If `checkpoint` is given, the search state is saved to that file every
+/// that many rounds, and when the search finishes.
+fn do_hash_search<W: Word>(
+    search: search::Search<W>,
+    checkpoint: Option<(&Path, usize)>,
+    image: Option<(&Path, &StatsImageOptions)>,
+) {
+    let mut search = search;
+
+    println!("Seed: {}", search.seed);
+    println!();
+    experiments::run_search(
+        &mut search,
+        checkpoint,
+        |search| {
+            print!(
+                "\rround {}/{}, best score {:.6}",
+                search.round,
+                search.rounds,
+                search.best().score
+            );
+            std::io::stdout().flush().unwrap();
+        },
+        |e| {
+            eprintln!(
+                "\nCouldn't write checkpoint \"{}\": {}",
+                checkpoint.unwrap().0.display(),
+                e
+            );
+        },
+    );
+    println!();
+
+    // Print out the top hashes, and (optionally) write statistics png images
+    // for them as well.
+    for (i, c) in search.candidates.iter().enumerate() {
+        println!("Score: {}", c.score);
+
+        println!("Hash: {}", format_hash_ops(&c.ops));
+        print_stats(&c.stats);
+        println!();
+
+        if let Some((dir, options)) = image {
+            write_stats_image(
+                &c.stats,
+                options,
+                &mut File::create(dir.join(format!("candidate_{:02}.png", i + 1))).unwrap(),
+            );
+        }
+    }
 }
@@ -0,0 +1,289 @@
+//! Checks of the net properties of point sets.
+//!
+//! A set of `2^m` points in `s` dimensions is a (t,m,s)-net in base 2 if
+//! every elementary interval of volume `2^(t-m)` contains exactly `2^t`
+//! points.  The elementary intervals are the cells of the grids with
+//! `2^k_1 x ... x 2^k_s` cells where `k_1 + ... + k_s = m - t`.  Every
+//! aligned power-of-two block of the Sobol sequence is a net, and a correct
+//! Owen scramble must preserve that with the same `t`.
+//!
+//! Coordinates are 0.32 fixed point numbers, since converting them to
+//! floats can round points across cell boundaries.
+
+use rayon::prelude::*;
+
+use crate::sobol;
+
+/// A grid shape with cells that don't contain the right number of points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The log2 of the number of cells along each dimension.
+    pub shape: Vec<u32>,
+
+    /// How many of the cells have the wrong number of points.
+    pub bad_cells: usize,
+}
+
+/// The seeds for which a scrambled point set isn't a net, at one point
+/// count.  See `scrambled_net_failures()`.
+#[derive(Debug, Clone, Default)]
+pub struct NetFailures {
+    /// How many of the seeds give points that aren't nets.
+    pub seeds: usize,
+
+    /// The first violation of the first of those seeds.
+    pub first: Option<Violation>,
+}
+
+/// Checks whether the given points form a (t,m,s)-net, where the point
+/// count is `2^m`.
+///
+/// The points are given as one slice of coordinates per dimension.
+/// Returns the grid shapes that violate the net property, so an empty list
+/// means it's a net.  A `t` greater than `m` is always satisfied.
+pub fn net_violations(points: &[&[u32]], t: u32) -> Vec<Violation> {
+    let n = points.first().map(|p| p.len()).unwrap_or(0);
+    assert!(n.is_power_of_two());
+    assert!(points.iter().all(|p| p.len() == n));
+    let m = n.trailing_zeros();
+    if t > m {
+        return Vec::new();
+    }
+
+    let mut violations = Vec::new();
+    let mut counts = vec![0u32; 1 << (m - t)];
+    let mut shape = vec![0u32; points.len()];
+    for_each_shape(&mut shape, 0, m - t, &mut |shape| {
+        counts.iter_mut().for_each(|c| *c = 0);
+        for i in 0..n {
+            let cell = points
+                .iter()
+                .zip(shape.iter())
+                .fold(0usize, |cell, (p, &bits)| {
+                    (cell << bits) | coordinate_cell(p[i], bits)
+                });
+            counts[cell] += 1;
+        }
+        let bad_cells = counts.iter().filter(|&&c| c != 1 << t).count();
+        if bad_cells > 0 {
+            violations.push(Violation {
+                shape: shape.to_vec(),
+                bad_cells,
+            });
+        }
+    });
+    violations
+}
+
+/// The smallest `t` for which the given points form a (t,m,s)-net.  See
+/// `net_violations()`.
+pub fn quality_parameter(points: &[&[u32]]) -> u32 {
+    (0..)
+        .find(|&t| net_violations(points, t).is_empty())
+        .unwrap()
+}
+
+/// Checks every power-of-two prefix of the given points for being a
+/// (t,m,s)-net, from a single point up to all of them.
+///
+/// The points are given like for `net_violations()`.  Item `m` of the
+/// result is whether the first `2^m` points are a net.
+pub fn prefixes_are_nets(points: &[&[u32]], t: u32) -> Vec<bool> {
+    let n = points.first().map(|p| p.len()).unwrap_or(0);
+    assert!(n.is_power_of_two());
+    (0..=n.trailing_zeros())
+        .map(|m| {
+            let prefixes: Vec<&[u32]> = points.iter().map(|p| &p[..(1 << m)]).collect();
+            net_violations(&prefixes, t).is_empty()
+        })
+        .collect()
+}
+
+/// Checks that every power-of-two prefix of the first two dimensions of
+/// `sobol::sample_owen_shuffled_u32()` with `seed`, up to `2^log2_points`
+/// points, is a (0,m,2)-net.  The result is like `prefixes_are_nets()`.
+pub fn shuffled_prefixes_are_nets(seed: u32, log2_points: u32) -> Vec<bool> {
+    let xs: Vec<u32> = (0..(1 << log2_points))
+        .map(|i| sobol::sample_owen_shuffled_u32(i, 0, seed))
+        .collect();
+    let ys: Vec<u32> = (0..(1 << log2_points))
+        .map(|i| sobol::sample_owen_shuffled_u32(i, 1, seed))
+        .collect();
+    prefixes_are_nets(&[&xs, &ys], 0)
+}
+
+/// Same as `shuffled_prefixes_are_nets()`, but with the index shuffled
+/// independently for each dimension, with seeds from
+/// `sobol::dimension_seed()`.  That's wrong on purpose, as a control: the
+/// prefixes pair up the wrong coordinates, so they lose their
+/// stratification.
+pub fn independently_shuffled_prefixes_are_nets(seed: u32, log2_points: u32) -> Vec<bool> {
+    let sample = |i, d| sobol::sample_owen_shuffled_u32(i, d, sobol::dimension_seed(d, seed));
+    let xs: Vec<u32> = (0..(1 << log2_points)).map(|i| sample(i, 0)).collect();
+    let ys: Vec<u32> = (0..(1 << log2_points)).map(|i| sample(i, 1)).collect();
+    prefixes_are_nets(&[&xs, &ys], 0)
+}
+
+/// Counts the seeds for which each power-of-two prefix, up to
+/// `2^log2_points` points, isn't a net.  `prefixes_are_nets` gives the
+/// result of `prefixes_are_nets()` for a seed, e.g. with
+/// `shuffled_prefixes_are_nets()`.
+pub fn prefix_net_failures<F>(prefixes_are_nets: F, log2_points: u32, seeds: &[u32]) -> Vec<usize>
+where
+    F: Fn(u32) -> Vec<bool> + Sync, // seed -> whether each prefix is a net
+{
+    seeds
+        .par_iter()
+        .map(|&s| {
+            prefixes_are_nets(s)
+                .iter()
+                .map(|&is_net| !is_net as usize)
+                .collect::<Vec<usize>>()
+        })
+        .reduce(
+            || vec![0; log2_points as usize + 1],
+            |mut a, b| {
+                for (a, b) in a.iter_mut().zip(b.iter()) {
+                    *a += b;
+                }
+                a
+            },
+        )
+}
+
+/// The `t` of every power-of-two prefix of the unscrambled Sobol sequence
+/// in the given dimensions, up to `2^log2_points` points.  Item `m` is for
+/// the first `2^m` points.
+pub fn sobol_quality_parameters(dimensions: &[u32], log2_points: u32) -> Vec<u32> {
+    let points = sample_points(|i, d, _| sobol::sobol_u32(i, d), dimensions, log2_points, 0);
+    (0..=log2_points)
+        .map(|m| quality_parameter(&prefixes(&points, m)))
+        .collect()
+}
+
+/// Checks that scrambling preserves the net properties of the Sobol
+/// sequence, by checking every power-of-two prefix of the scrambled
+/// points with each seed against the `t` of the unscrambled points, `ts`,
+/// from `sobol_quality_parameters()`.
+///
+/// `sample` gives the scrambled points in the given dimensions, with the
+/// seed of the whole point set.  Item `m` of the result is for the first
+/// `2^m` points.
+pub fn scrambled_net_failures<F>(
+    sample: F,
+    dimensions: &[u32],
+    ts: &[u32],
+    seeds: &[u32],
+) -> Vec<NetFailures>
+where
+    F: Fn(u32, u32, u32) -> u32 + Sync, // (sample_index, dimension, seed) -> coordinate
+{
+    let log2_points = ts.len() as u32 - 1;
+    let per_seed: Vec<Vec<Option<Violation>>> = seeds
+        .par_iter()
+        .map(|&s| {
+            let points = sample_points(&sample, dimensions, log2_points, s);
+            ts.iter()
+                .enumerate()
+                .map(|(m, &t)| {
+                    net_violations(&prefixes(&points, m as u32), t)
+                        .into_iter()
+                        .next()
+                })
+                .collect()
+        })
+        .collect();
+    (0..ts.len())
+        .map(|m| {
+            let mut violations = per_seed.iter().filter_map(|v| v[m].as_ref());
+            let first = violations.next().cloned();
+            NetFailures {
+                seeds: first.iter().count() + violations.count(),
+                first,
+            }
+        })
+        .collect()
+}
+
+/// The first `2^log2_points` points of `sample` with `seed`, as one list
+/// of coordinates per dimension.
+fn sample_points<F>(sample: F, dimensions: &[u32], log2_points: u32, seed: u32) -> Vec<Vec<u32>>
+where
+    F: Fn(u32, u32, u32) -> u32, // (sample_index, dimension, seed) -> coordinate
+{
+    dimensions
+        .iter()
+        .map(|&d| {
+            (0..(1 << log2_points))
+                .map(|i| sample(i, d, seed))
+                .collect()
+        })
+        .collect()
+}
+
+/// The first `2^m` of the given points.
+fn prefixes(points: &[Vec<u32>], m: u32) -> Vec<&[u32]> {
+    points.iter().map(|p| &p[..(1 << m)]).collect()
+}
+
+/// Calls `f` with every way of splitting `bits` between the dimensions of
+/// `shape` from `dim` on.
+fn for_each_shape<F: FnMut(&[u32])>(shape: &mut [u32], dim: usize, bits: u32, f: &mut F) {
+    if dim + 1 >= shape.len() {
+        if let Some(last) = shape.last_mut() {
+            *last = bits;
+        }
+        f(shape);
+        return;
+    }
+    for b in 0..=bits {
+        shape[dim] = b;
+        for_each_shape(shape, dim + 1, bits - b, f);
+    }
+}
+
+/// The index of the cell that fixed point coordinate `x` falls in, when
+/// the unit interval is divided into `2^bits` cells.
+fn coordinate_cell(x: u32, bits: u32) -> usize {
+    if bits == 0 {
+        0
+    } else {
+        (x >> (32 - bits)) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEEDS: [u32; 6] = [0, 1, 2, 12345, 0x9e3779b9, 0xffffffff];
+
+    #[test]
+    fn shuffled_prefixes_are_nets_for_all_m() {
+        for &seed in SEEDS.iter() {
+            let nets = shuffled_prefixes_are_nets(seed, 10);
+            assert_eq!(nets.len(), 11);
+            for (m, &is_net) in nets.iter().enumerate() {
+                assert!(is_net, "seed {}: the first {} points", seed, 1 << m);
+            }
+        }
+    }
+
+    #[test]
+    fn independently_shuffled_prefixes_are_not_nets() {
+        for &seed in SEEDS.iter() {
+            let nets = independently_shuffled_prefixes_are_nets(seed, 8);
+            assert!(nets[0] && nets[1]);
+            assert!(nets[2..].iter().any(|&is_net| !is_net), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn sobol_prefixes_are_nets() {
+        let xs: Vec<u32> = (0..(1 << 10)).map(|i| sobol::sobol_u32(i, 0)).collect();
+        let ys: Vec<u32> = (0..(1 << 10)).map(|i| sobol::sobol_u32(i, 1)).collect();
+        assert!(prefixes_are_nets(&[&xs, &ys], 0)
+            .iter()
+            .all(|&is_net| is_net));
+    }
+}
@@ -0,0 +1,80 @@
+//! A small seedable PRNG whose state can be saved and restored.
+//!
+//! This is PCG32 (XSH-RR), from <https://www.pcg-random.org>.  It implements
+//! `rand::RngCore`, so all of the usual `rand::Rng` methods work with it.
+
+use rand::{Rng, RngCore};
+
+const MULTIPLIER: u64 = 6364136223846793005;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Pcg32 {
+    state: u64,
+    increment: u64, // Always odd.
+}
+
+impl Pcg32 {
+    pub fn new(seed: u64) -> Pcg32 {
+        Pcg32::with_stream(seed, 0x6d1f1ce5ca5cadad)
+    }
+
+    /// Creates a PRNG on one of 2^63 independent streams.  PRNGs with the
+    /// same seed but different streams produce unrelated sequences.
+    pub fn with_stream(seed: u64, stream: u64) -> Pcg32 {
+        let mut rng = Pcg32 {
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    /// Restores a PRNG from a state previously returned by `state()`.
+    pub fn from_state(state: (u64, u64)) -> Pcg32 {
+        Pcg32 {
+            state: state.0,
+            increment: state.1 | 1,
+        }
+    }
+
+    /// The full internal state of the PRNG.
+    pub fn state(&self) -> (u64, u64) {
+        (self.state, self.increment)
+    }
+}
+
+impl RngCore for Pcg32 {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(self.increment);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        (self.next_u32() as u64) | ((self.next_u32() as u64) << 32)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Draws `count` 32-bit seeds from a `Pcg32` with the given seed.  This is
+/// how the experiments pick the seeds of their point sets.
+pub fn seeds(seed: u64, count: usize) -> Vec<u32> {
+    let mut rng = Pcg32::new(seed);
+    (0..count).map(|_| rng.gen()).collect()
+}
@@ -0,0 +1,122 @@
+//! The scrambled Sobol point sets that the point set experiments compare.
+
+use crate::hashes::SelectedHash;
+use crate::sobol;
+
+/// How a `Sampler` scrambles the Sobol sequence.
+#[derive(Copy, Clone)]
+enum Scramble<'a> {
+    None,
+    Fast,
+    Hash(&'a SelectedHash),
+    Reference,
+}
+
+/// A Sobol point set, either unscrambled or Owen scrambled in one of the
+/// ways provided by `sobol`, along with a name to print it by.
+#[derive(Copy, Clone)]
+pub struct Sampler<'a> {
+    pub name: &'a str,
+    scramble: Scramble<'a>,
+}
+
+impl<'a> Sampler<'a> {
+    /// The unscrambled Sobol sequence.  The seed is ignored.
+    pub fn unscrambled() -> Sampler<'static> {
+        Sampler {
+            name: "unscrambled",
+            scramble: Scramble::None,
+        }
+    }
+
+    /// Owen scrambled with `hash`, or as in `sobol::sample_owen_fast()` if
+    /// it's `None`.
+    pub fn owen(hash: Option<&'a SelectedHash>) -> Sampler<'a> {
+        match hash {
+            Some(h) => Sampler {
+                name: h.name(),
+                scramble: Scramble::Hash(h),
+            },
+            None => Sampler {
+                name: sobol::FAST_HASH,
+                scramble: Scramble::Fast,
+            },
+        }
+    }
+
+    /// Owen scrambled with the slow "ground truth" scramble, as in
+    /// `sobol::sample_owen_reference()`.
+    pub fn reference() -> Sampler<'static> {
+        Sampler {
+            name: "reference",
+            scramble: Scramble::Reference,
+        }
+    }
+
+    /// Point `index` of the point set with the given seed.
+    ///
+    /// Each dimension is scrambled with its own seed, derived from `seed`
+    /// with `sobol::dimension_seed()`, like `sobol::sample_owen()` does.
+    #[inline]
+    pub fn sample(&self, index: u32, dimension: u32, seed: u32) -> f32 {
+        self.scramble(index, dimension, sobol::dimension_seed(dimension, seed))
+    }
+
+    /// Same as `sample()`, but returns the coordinate as a 0.32 fixed point
+    /// number.
+    #[inline]
+    pub fn sample_u32(&self, index: u32, dimension: u32, seed: u32) -> u32 {
+        self.scramble_u32(index, dimension, sobol::dimension_seed(dimension, seed))
+    }
+
+    /// Same as `sample()`, except `seed` is used as-is for the dimension,
+    /// rather than being the seed of the whole point set.
+    #[inline]
+    pub fn scramble(&self, index: u32, dimension: u32, seed: u32) -> f32 {
+        match self.scramble {
+            Scramble::None => sobol::sample(index, dimension),
+            Scramble::Fast => sobol::sample_owen_fast(index, dimension, seed),
+            Scramble::Hash(h) => {
+                sobol::sample_owen_hash(index, dimension, seed, |n, s| h.exec(n, s))
+            }
+            Scramble::Reference => sobol::sample_owen_reference(index, dimension, seed),
+        }
+    }
+
+    /// Same as `scramble()`, but returns the coordinate as a 0.32 fixed
+    /// point number.
+    #[inline]
+    pub fn scramble_u32(&self, index: u32, dimension: u32, seed: u32) -> u32 {
+        let x = sobol::sobol_u32(index, dimension);
+        match self.scramble {
+            Scramble::None => x,
+            Scramble::Fast => sobol::owen_scramble_fast_u32(x, seed),
+            Scramble::Hash(h) => sobol::owen_scramble_hash_u32(x, seed, |n, s| h.exec(n, s)),
+            Scramble::Reference => sobol::owen_scramble_reference_u32(x, seed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashes;
+
+    #[test]
+    fn default_owen_sampler_is_the_fast_hash() {
+        let hash = SelectedHash::Registered(hashes::get(sobol::FAST_HASH).unwrap());
+        let default = Sampler::owen(None);
+        let named = Sampler::owen(Some(&hash));
+        assert_eq!(default.name, named.name);
+        for seed in 0..4 {
+            for dimension in 0..4 {
+                for index in 0..256 {
+                    assert_eq!(
+                        default.sample_u32(index, dimension, seed),
+                        named.sample_u32(index, dimension, seed)
+                    );
+                }
+            }
+        }
+    }
+}
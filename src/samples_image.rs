@@ -0,0 +1,147 @@
+//! Images of 2D projections of point sets.
+
+use std::fs::File;
+
+/// Draws the projection onto dimensions `(dim_x, dim_y)` of the point set
+/// with the given seed, for each of `point_counts` side by side from left
+/// to right, and writes it as a png.
+///
+/// `seed` is passed to `sample` as-is for both dimensions, like for
+/// `spectrum::power_spectrum()`.
+pub fn write_samples_image<F>(
+    sample: F,
+    resolution: usize,
+    point_counts: &[u32],
+    (dim_x, dim_y): (u32, u32),
+    seed: u32,
+    file: &mut File,
+) where
+    F: Fn(u32, u32, u32) -> f32, // (sample_index, dimension, seed) -> coordinate
+{
+    const POINT_RADIUS: usize = 2;
+
+    let width = resolution * point_counts.len();
+    let height = resolution;
+    let mut image = vec![0xffu8; width * height * 4];
+
+    // Plot the points at the various point counts.
+    for (set_idx, &point_count) in point_counts.iter().enumerate() {
+        draw_points(
+            &mut image,
+            width,
+            (resolution * set_idx, 0),
+            resolution,
+            POINT_RADIUS,
+            |i| (sample(i, dim_x, seed), sample(i, dim_y, seed)),
+            point_count,
+        );
+    }
+
+    png_encode_mini::write_rgba_from_u8(file, &image, width as u32, height as u32).unwrap();
+}
+
+/// Same as `write_samples_image()`, but draws every pair of the first
+/// `dimensions` dimensions into a single contact-sheet image.
+///
+/// The sheet is a lower-triangular grid, with dimension `i` on the x axis
+/// in column `i`, and dimension `j` on the y axis in row `j - 1` from the
+/// top.
+pub fn write_samples_grid_image<F>(
+    sample: F,
+    cell_resolution: usize,
+    point_count: u32,
+    dimensions: u32,
+    seed: u32,
+    file: &mut File,
+) where
+    F: Fn(u32, u32, u32) -> f32, // (sample_index, dimension, seed) -> coordinate
+{
+    const POINT_RADIUS: usize = 1;
+    const BORDER_COLOR: u8 = 0xc0;
+
+    let cells = dimensions as usize - 1;
+    let width = cell_resolution * cells;
+    let height = width;
+    let mut image = vec![0xffu8; width * height * 4];
+
+    for j in 1..dimensions {
+        for i in 0..j {
+            // Png rows are stored bottom-up, so the top row is the last.
+            let origin = (
+                cell_resolution * i as usize,
+                cell_resolution * (cells - j as usize),
+            );
+
+            // Cell border.
+            for k in 0..cell_resolution {
+                for &(x, y) in [
+                    (k, 0),
+                    (k, cell_resolution - 1),
+                    (0, k),
+                    (cell_resolution - 1, k),
+                ]
+                .iter()
+                {
+                    let p = ((origin.1 + y) * width + origin.0 + x) * 4;
+                    image[p..(p + 3)].copy_from_slice(&[BORDER_COLOR; 3]);
+                }
+            }
+
+            draw_points(
+                &mut image,
+                width,
+                origin,
+                cell_resolution,
+                POINT_RADIUS,
+                |n| (sample(n, i, seed), sample(n, j, seed)),
+                point_count,
+            );
+        }
+    }
+
+    png_encode_mini::write_rgba_from_u8(file, &image, width as u32, height as u32).unwrap();
+}
+
+/// Draws `point_count` points from `point` into the `resolution` x
+/// `resolution` square of an RGBA image at `origin`.
+fn draw_points<F>(
+    image: &mut [u8],
+    image_width: usize,
+    origin: (usize, usize),
+    resolution: usize,
+    point_radius: usize,
+    point: F,
+    point_count: u32,
+) where
+    F: Fn(u32) -> (f32, f32), // sample_index -> (x, y)
+{
+    // Draws a point, clipped to the square.
+    let mut plot = |x: usize, y: usize| {
+        let min_x = x.saturating_sub(point_radius);
+        let min_y = y.saturating_sub(point_radius);
+        let max_x = (x + point_radius + 1).min(resolution);
+        let max_y = (y + point_radius + 1).min(resolution);
+
+        for yy in min_y..max_y {
+            for xx in min_x..max_x {
+                let x2 = x as isize - xx as isize;
+                let y2 = y as isize - yy as isize;
+                if (((x2 * x2) + (y2 * y2)) as f64).sqrt() <= point_radius as f64 {
+                    let p = ((origin.1 + yy) * image_width + origin.0 + xx) * 4;
+                    image[p] = 0x00;
+                    image[p + 1] = 0x00;
+                    image[p + 2] = 0x00;
+                    image[p + 3] = 0xFF;
+                }
+            }
+        }
+    };
+
+    for i in 0..point_count {
+        let (x, y) = point(i);
+        plot(
+            (x * (resolution - 1) as f32) as usize,
+            (y * (resolution - 1) as f32) as usize,
+        );
+    }
+}
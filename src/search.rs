@@ -0,0 +1,574 @@
+//! Strategies for searching for better hashes.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use rand::Rng;
+
+use crate::compiled_hash::BatchHash;
+use crate::hash_gen::{
+    crossover_hash_ops, format_hash_ops, mutate_hash_ops, parse_hash_ops, HashOp,
+};
+use crate::rng::Pcg32;
+use crate::stats::{measure_stats, score_stats, Stats};
+use crate::word::Word;
+
+/// How many of the best hashes to keep around.
+pub const CANDIDATE_COUNT: usize = 4;
+
+/// How many rounds of `measure_stats()` to score each hash with, unless
+/// the search says otherwise.
+pub const STAT_ROUNDS: u32 = 1 << 22;
+
+/// For simulated annealing, the temperature at the end of the search
+/// relative to the temperature at the start.  The temperature decays
+/// geometrically between the two.
+const ANNEAL_END_TEMPERATURE: f64 = 0.001;
+
+/// For simulated annealing, the starting temperature relative to the
+/// score of the first evaluated hash.
+const ANNEAL_START_TEMPERATURE: f64 = 0.1;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Strategy {
+    /// Give the template's operations new random constants, and keep
+    /// the best results.  No mutation or anything.
+    Random,
+
+    /// Mutate the best hashes found so far, keeping the mutations that
+    /// beat the worst of them.
+    HillClimb,
+
+    /// Like `HillClimb`, but half the time the new hash is bred from two
+    /// of the best hashes via crossover.
+    Evolve,
+
+    /// Simulated annealing: mutate a single current hash, accepting worse
+    /// hashes with a probability that decreases as the search progresses.
+    Anneal,
+}
+
+impl Strategy {
+    pub const NAMES: &'static [&'static str] = &["random", "hill-climb", "evolve", "anneal"];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Strategy::Random => "random",
+            Strategy::HillClimb => "hill-climb",
+            Strategy::Evolve => "evolve",
+            Strategy::Anneal => "anneal",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Strategy> {
+        match name {
+            "random" => Some(Strategy::Random),
+            "hill-climb" => Some(Strategy::HillClimb),
+            "evolve" => Some(Strategy::Evolve),
+            "anneal" => Some(Strategy::Anneal),
+            _ => None,
+        }
+    }
+}
+
+/// A scored hash.
+#[derive(Debug, Clone)]
+pub struct Candidate<W = u32> {
+    pub ops: Vec<HashOp<W>>,
+    pub score: f64,
+    pub stats: Stats,
+}
+
+impl<W: Word> Candidate<W> {
+    /// Measures the stats of the given hash, and scores it.
+    ///
+    /// `stat_rounds` and `stats_seed` are passed on to `measure_stats()`.
+    pub fn evaluate(ops: Vec<HashOp<W>>, stat_rounds: u32, stats_seed: u64) -> Candidate<W> {
+        let hash = BatchHash::new(&ops);
+        let stats = measure_stats(
+            |inputs, seed, outputs| hash.exec(inputs, seed, outputs),
+            stat_rounds,
+            stats_seed,
+            false,
+        );
+        let score = score_stats(&stats);
+        Candidate { ops, score, stats }
+    }
+
+    fn is_evaluated(&self) -> bool {
+        self.score.is_finite()
+    }
+}
+
+/// The state of a search for hashes on `W`-sized words.
+pub struct Search<W = u32> {
+    pub strategy: Strategy,
+    pub template: Vec<HashOp<W>>,
+    pub round: usize,
+    pub rounds: usize,
+    pub rng: Pcg32,
+
+    /// The seed `rng` was started from.
+    pub seed: u64,
+
+    /// How many rounds of `measure_stats()` to score each hash with.
+    pub stat_rounds: u32,
+
+    /// The best hashes found so far, best first.
+    pub candidates: Vec<Candidate<W>>,
+
+    /// The current hash and starting temperature for simulated annealing.
+    pub current: Option<Candidate<W>>,
+    pub start_temperature: f64,
+}
+
+impl<W: Word> Search<W> {
+    /// Creates a new search that runs for `rounds` rounds, starting from
+    /// `template`, with its random numbers generated from `seed`.
+    pub fn new(strategy: Strategy, template: &[HashOp<W>], rounds: usize, seed: u64) -> Search<W> {
+        let mut rng = Pcg32::new(seed);
+        let candidates = (0..CANDIDATE_COUNT)
+            .map(|_| Candidate {
+                ops: randomize_constants(template, &mut rng),
+                score: f64::INFINITY,
+                stats: Stats::zero(W::BITS as usize),
+            })
+            .collect();
+
+        Search {
+            strategy,
+            template: template.to_vec(),
+            round: 0,
+            rounds,
+            rng,
+            seed,
+            stat_rounds: STAT_ROUNDS,
+            candidates,
+            current: None,
+            start_temperature: 0.0,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.round >= self.rounds
+    }
+
+    pub fn best(&self) -> &Candidate<W> {
+        &self.candidates[0]
+    }
+
+    /// Generates and scores one new hash.
+    pub fn step(&mut self) {
+        let nothing_evaluated = !self.candidates.iter().any(|c| c.is_evaluated());
+        let new_hash = match self.strategy {
+            Strategy::Random => randomize_constants(&self.template, &mut self.rng),
+
+            // Start the other strategies from the template itself.
+            _ if nothing_evaluated => self.template.clone(),
+
+            Strategy::HillClimb => {
+                let parent = self.pick_parent();
+                mutate_hash_ops(&parent, &mut self.rng)
+            }
+            Strategy::Evolve => {
+                let a = self.pick_parent();
+                let b = self.pick_parent();
+                let child = crossover_hash_ops(&a, &b, &mut self.rng);
+                if self.rng.gen::<bool>() && child != a && child != b {
+                    child
+                } else {
+                    mutate_hash_ops(&a, &mut self.rng)
+                }
+            }
+            Strategy::Anneal => {
+                let parent = match self.current {
+                    Some(ref c) => c.ops.clone(),
+                    None => self.pick_parent(),
+                };
+                mutate_hash_ops(&parent, &mut self.rng)
+            }
+        };
+        let new = Candidate::evaluate(new_hash, self.stat_rounds, self.rng.gen());
+
+        // Simulated annealing acceptance.
+        if self.strategy == Strategy::Anneal {
+            let accept = match self.current {
+                None if !new.score.is_finite() => false,
+                None => {
+                    self.start_temperature = new.score * ANNEAL_START_TEMPERATURE;
+                    true
+                }
+                Some(ref c) => {
+                    let t = self.start_temperature
+                        * ANNEAL_END_TEMPERATURE.powf(self.round as f64 / self.rounds as f64);
+                    new.score < c.score
+                        || self.rng.gen::<f64>() < (-(new.score - c.score) / t).exp()
+                }
+            };
+            if accept {
+                self.current = Some(new.clone());
+            }
+        }
+
+        // If it beats the current lowest-scoring hash, replace it.  A NaN
+        // score never compares as less, so it's never kept.
+        let last_idx = self.candidates.len() - 1;
+        if new.score < self.candidates[last_idx].score
+            && !self.candidates.iter().any(|c| c.ops == new.ops)
+        {
+            self.candidates[last_idx] = new;
+            self.candidates
+                .sort_unstable_by(|x, y| x.score.total_cmp(&y.score));
+        }
+
+        self.round += 1;
+    }
+
+    /// Picks one of the best hashes found so far at random, or the
+    /// template if nothing has been scored yet.
+    fn pick_parent(&mut self) -> Vec<HashOp<W>> {
+        let evaluated: Vec<_> = self
+            .candidates
+            .iter()
+            .filter(|c| c.is_evaluated())
+            .collect();
+        if evaluated.is_empty() {
+            self.template.clone()
+        } else {
+            evaluated[self.rng.gen::<usize>() % evaluated.len()]
+                .ops
+                .clone()
+        }
+    }
+}
+
+/// Gives all of the operations of a hash new random constants.
+fn randomize_constants<W: Word>(hash_ops: &[HashOp<W>], rng: &mut Pcg32) -> Vec<HashOp<W>> {
+    hash_ops.iter().map(|op| op.new_constant(rng)).collect()
+}
+
+//----------------------------------------------------------------------
+// Checkpointing.
+//
+// A checkpoint is a plain text file with one `key value...` entry per
+// line.  Hashes are stored in the textual hash format, and floats are
+// written with enough precision to round-trip exactly.  Each `candidate`
+// or `current` line starts a new scored hash, and the `ops`, `avalanche`,
+// `avalanche_avg_bias` and `tree_bias` lines that follow belong to it.
+// The stats matrices are written row by row, indexed `[bit_in][bit_out]`.
+// The `bits` entry gives the word size of the hashes.
+
+impl<W: Word> Search<W> {
+    /// Writes the full state of the search to a checkpoint file.
+    ///
+    /// The checkpoint is first written to a temporary file and then
+    /// renamed over `path`, so an interrupted write never clobbers the
+    /// previous checkpoint.
+    pub fn write_checkpoint(&self, path: &Path) -> std::io::Result<()> {
+        let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+        tmp_name.push(".tmp");
+        let tmp_path = path.with_file_name(tmp_name);
+        {
+            let mut f = BufWriter::new(File::create(&tmp_path)?);
+            writeln!(f, "bits {}", W::BITS)?;
+            writeln!(f, "strategy {}", self.strategy.name())?;
+            writeln!(f, "template {}", format_hash_ops(&self.template))?;
+            writeln!(f, "round {}", self.round)?;
+            writeln!(f, "rounds {}", self.rounds)?;
+            writeln!(f, "stat_rounds {}", self.stat_rounds)?;
+            writeln!(f, "seed {}", self.seed)?;
+            let (state, increment) = self.rng.state();
+            writeln!(f, "rng {} {}", state, increment)?;
+            writeln!(f, "start_temperature {}", self.start_temperature)?;
+            for c in self.candidates.iter() {
+                write_candidate(&mut f, "candidate", c)?;
+            }
+            if let Some(ref c) = self.current {
+                write_candidate(&mut f, "current", c)?;
+            }
+            f.flush()?;
+        }
+        std::fs::rename(&tmp_path, path)
+    }
+
+    /// Restores a search from a checkpoint file written by
+    /// `write_checkpoint()`.  The checkpoint must be for hashes on
+    /// `W`-sized words.
+    pub fn read_checkpoint(path: &Path) -> Result<Search<W>, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("couldn't read \"{}\": {}", path.display(), e))?;
+
+        let mut strategy = None;
+        let mut template = None;
+        let mut round = None;
+        let mut rounds = None;
+        let mut stat_rounds = STAT_ROUNDS;
+        let mut rng = None;
+        let mut seed = None;
+        let mut has_bits = false;
+        let mut start_temperature = 0.0;
+        let mut scored: Vec<(bool, Candidate<W>)> = Vec::new(); // (is_current, candidate)
+        let bits = W::BITS as usize;
+
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.find(' ') {
+                Some(i) => (&line[..i], line[i..].trim()),
+                None => (line, ""),
+            };
+            let err = |msg: String| format!("{}:{}: {}", path.display(), line_number + 1, msg);
+
+            match key {
+                "bits" => {
+                    let checkpoint_bits: usize = parse_value(value).map_err(err)?;
+                    if checkpoint_bits != bits {
+                        return Err(err(format!(
+                            "checkpoint is for {}-bit hashes, not {}-bit",
+                            checkpoint_bits, bits
+                        )));
+                    }
+                    has_bits = true;
+                }
+                "strategy" => {
+                    strategy = Some(
+                        Strategy::from_name(value)
+                            .ok_or_else(|| err(format!("unknown strategy \"{}\"", value)))?,
+                    )
+                }
+                "template" => {
+                    template = Some(parse_hash_ops(value).map_err(|e| err(e.to_string()))?)
+                }
+                "round" => round = Some(parse_value(value).map_err(err)?),
+                "rounds" => rounds = Some(parse_value(value).map_err(err)?),
+                "stat_rounds" => stat_rounds = parse_value(value).map_err(err)?,
+                "seed" => seed = Some(parse_value(value).map_err(err)?),
+                "rng" => {
+                    let mut parts = value.split_whitespace();
+                    let state = parse_value(parts.next().unwrap_or("")).map_err(err)?;
+                    let increment = parse_value(parts.next().unwrap_or("")).map_err(err)?;
+                    rng = Some(Pcg32::from_state((state, increment)));
+                }
+                "start_temperature" => start_temperature = parse_value(value).map_err(err)?,
+                "candidate" | "current" => scored.push((
+                    key == "current",
+                    Candidate {
+                        ops: Vec::new(),
+                        score: parse_value(value).map_err(err)?,
+                        stats: Stats::zero(bits),
+                    },
+                )),
+                "ops" | "avalanche" | "avalanche_avg_bias" | "tree_bias" => {
+                    let c = &mut scored
+                        .last_mut()
+                        .ok_or_else(|| err(format!("\"{}\" before any candidate", key)))?
+                        .1;
+                    match key {
+                        "ops" => c.ops = parse_hash_ops(value).map_err(|e| err(e.to_string()))?,
+                        "avalanche" => {
+                            parse_matrix(value, &mut c.stats.avalanche, bits).map_err(err)?
+                        }
+                        "avalanche_avg_bias" => {
+                            parse_matrix(value, &mut c.stats.avalanche_avg_bias, bits)
+                                .map_err(err)?
+                        }
+                        _ => parse_matrix(value, &mut c.stats.tree_bias, 32).map_err(err)?,
+                    }
+                }
+                _ => return Err(err(format!("unknown entry \"{}\"", key))),
+            }
+        }
+
+        let missing = |what: &str| format!("{}: missing \"{}\"", path.display(), what);
+        if !has_bits {
+            return Err(missing("bits"));
+        }
+        let mut current = None;
+        let mut candidates = Vec::new();
+        for (is_current, c) in scored {
+            if is_current {
+                current = Some(c);
+            } else {
+                candidates.push(c);
+            }
+        }
+        if candidates.is_empty() {
+            return Err(missing("candidate"));
+        }
+
+        Ok(Search {
+            strategy: strategy.ok_or_else(|| missing("strategy"))?,
+            template: template.ok_or_else(|| missing("template"))?,
+            round: round.ok_or_else(|| missing("round"))?,
+            rounds: rounds.ok_or_else(|| missing("rounds"))?,
+            rng: rng.ok_or_else(|| missing("rng"))?,
+            seed: seed.ok_or_else(|| missing("seed"))?,
+            stat_rounds,
+            candidates,
+            current,
+            start_temperature,
+        })
+    }
+}
+
+fn write_candidate<F: Write, W: Word>(
+    f: &mut F,
+    key: &str,
+    c: &Candidate<W>,
+) -> std::io::Result<()> {
+    let bits = c.stats.bits;
+    writeln!(f, "{} {}", key, c.score)?;
+    writeln!(f, "ops {}", format_hash_ops(&c.ops))?;
+    write_matrix(f, "avalanche", &c.stats.avalanche, bits)?;
+    write_matrix(f, "avalanche_avg_bias", &c.stats.avalanche_avg_bias, bits)?;
+    write_matrix(f, "tree_bias", &c.stats.tree_bias, 32)
+}
+
+/// Writes the top-left `size` x `size` corner of a matrix.
+fn write_matrix<F: Write, const N: usize>(
+    f: &mut F,
+    key: &str,
+    m: &[[f64; N]; N],
+    size: usize,
+) -> std::io::Result<()> {
+    write!(f, "{}", key)?;
+    for row in m[..size].iter() {
+        for v in row[..size].iter() {
+            write!(f, " {}", v)?;
+        }
+    }
+    writeln!(f)
+}
+
+/// Parses the top-left `size` x `size` corner of a matrix.
+fn parse_matrix<const N: usize>(
+    text: &str,
+    m: &mut [[f64; N]; N],
+    size: usize,
+) -> Result<(), String> {
+    let values = text
+        .split_whitespace()
+        .map(parse_value)
+        .collect::<Result<Vec<f64>, _>>()?;
+    if values.len() != size * size {
+        return Err(format!(
+            "expected {} values, got {}",
+            size * size,
+            values.len()
+        ));
+    }
+    for (i, v) in values.into_iter().enumerate() {
+        m[i / size][i % size] = v;
+    }
+    Ok(())
+}
+
+fn parse_value<T: std::str::FromStr>(text: &str) -> Result<T, String> {
+    text.parse()
+        .map_err(|_| format!("invalid value \"{}\"", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashes;
+
+    fn assert_same_candidate(a: &Candidate, b: &Candidate) {
+        assert_eq!(a.ops, b.ops);
+        assert_eq!(a.score.to_bits(), b.score.to_bits());
+        assert_eq!(a.stats.bits, b.stats.bits);
+        assert_eq!(a.stats.avalanche, b.stats.avalanche);
+        assert_eq!(a.stats.avalanche_avg_bias, b.stats.avalanche_avg_bias);
+        assert_eq!(a.stats.tree_bias, b.stats.tree_bias);
+    }
+
+    fn assert_same_search(a: &Search, b: &Search) {
+        assert_eq!(a.strategy, b.strategy);
+        assert_eq!(a.template, b.template);
+        assert_eq!(a.round, b.round);
+        assert_eq!(a.rounds, b.rounds);
+        assert_eq!(a.rng, b.rng);
+        assert_eq!(a.seed, b.seed);
+        assert_eq!(a.stat_rounds, b.stat_rounds);
+        assert_eq!(a.candidates.len(), b.candidates.len());
+        for (ca, cb) in a.candidates.iter().zip(b.candidates.iter()) {
+            assert_same_candidate(ca, cb);
+        }
+        assert_eq!(a.current.is_some(), b.current.is_some());
+        if let (Some(ca), Some(cb)) = (&a.current, &b.current) {
+            assert_same_candidate(ca, cb);
+        }
+        assert_eq!(a.start_temperature.to_bits(), b.start_temperature.to_bits());
+    }
+
+    #[test]
+    fn resumed_search_continues_like_an_uninterrupted_one() {
+        let template = hashes::get::<u32>("fast").unwrap().ops.unwrap();
+        let path =
+            std::env::temp_dir().join(format!("sample_test_checkpoint_{}.txt", std::process::id()));
+        for &strategy in [Strategy::Evolve, Strategy::Anneal].iter() {
+            let new_search = || {
+                let mut search = Search::<u32>::new(strategy, template, 6, 5);
+                search.stat_rounds = 256;
+                search
+            };
+
+            let mut uninterrupted = new_search();
+            while !uninterrupted.is_done() {
+                uninterrupted.step();
+            }
+
+            let mut interrupted = new_search();
+            for _ in 0..3 {
+                interrupted.step();
+            }
+            interrupted.write_checkpoint(&path).unwrap();
+            let mut resumed = Search::<u32>::read_checkpoint(&path).unwrap();
+            assert_same_search(&resumed, &interrupted);
+
+            while !resumed.is_done() {
+                resumed.step();
+            }
+            assert_same_search(&resumed, &uninterrupted);
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Checks that a checkpoint with its `key` line removed is rejected.
+    fn check_required_entry(key: &str) {
+        let template = hashes::get::<u32>("fast").unwrap().ops.unwrap();
+        let path = std::env::temp_dir().join(format!(
+            "sample_test_checkpoint_{}_{}.txt",
+            key,
+            std::process::id()
+        ));
+        Search::<u32>::new(Strategy::HillClimb, template, 6, 5)
+            .write_checkpoint(&path)
+            .unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let prefix = format!("{} ", key);
+        let stripped: String = text
+            .lines()
+            .filter(|line| !line.starts_with(&prefix))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        assert_ne!(stripped, text);
+        std::fs::write(&path, stripped).unwrap();
+        let result = Search::<u32>::read_checkpoint(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(result.is_err(), "{}", key);
+    }
+
+    #[test]
+    fn checkpoints_without_a_seed_are_rejected() {
+        check_required_entry("seed");
+    }
+
+    #[test]
+    fn checkpoints_without_bits_are_rejected() {
+        check_required_entry("bits");
+    }
+}
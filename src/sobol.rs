@@ -1,8 +1,20 @@
 //! An implementation of the Sobol low discrepancy sequence.
+//!
+//! The sequence is 32-bit only: indices and coordinates are `u32`, and the
+//! direction numbers are 32-bit.  So there is no 64-bit `sample_owen()`.
+//! `owen_scramble_reference()` and the hashes in `hashes::HASHES_64` do
+//! work on 64-bit words, for testing and searching 64-bit scrambles, but
+//! sampling with them would need 64-bit direction numbers.
 
-use super::hash_u32;
+use std::borrow::Cow;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::OnceLock;
 
-use super::hash_gen::{exec_hash_slice, HashOp};
+use crate::direction_numbers;
+use crate::hash_u32;
+use crate::hashes;
+use crate::word::Word;
 
 // The following `include` provides `MAX_DIMENSION` and `VECTORS`.
 // See the build.rs file for how this included file is generated.
@@ -24,6 +36,55 @@ pub fn sample_owen_fast(index: u32, dimension: u32, seed: u32) -> f32 {
     u32_to_0_1_f32(owen_scramble_fast_u32(sobol_u32(index, dimension), seed))
 }
 
+/// Same as `sample_owen_fast()`, except `seed` is the seed of the whole
+/// point set rather than of a single dimension.
+///
+/// Each dimension is scrambled with its own seed, derived from `seed` with
+/// `dimension_seed()`.  Prefer this over inventing per-dimension seeds by
+/// hand: using the same or closely related seeds for different dimensions
+/// makes their scrambles correlated.
+#[inline]
+pub fn sample_owen(index: u32, dimension: u32, seed: u32) -> f32 {
+    sample_owen_fast(index, dimension, dimension_seed(dimension, seed))
+}
+
+/// Same as `sample_owen()`, except the sample index is also Owen scrambled
+/// before evaluating the Sobol sequence, i.e. "Owen scrambling and
+/// shuffling" from Burley's "Practical Hash-based Owen Scrambling".
+///
+/// The index is shuffled the same way for all dimensions, so the points
+/// stay intact and only their order changes.  Any power-of-two sized
+/// prefix of the shuffled sequence is an aligned block of the unshuffled
+/// one, so it keeps the net properties of the Sobol sequence.  This makes
+/// it possible to pad dimensions with independently shuffled sequences,
+/// and to use a prefix of the sequence progressively.
+#[inline]
+pub fn sample_owen_shuffled(index: u32, dimension: u32, seed: u32) -> f32 {
+    u32_to_0_1_f32(sample_owen_shuffled_u32(index, dimension, seed))
+}
+
+/// Same as `sample_owen_shuffled()`, but returns the coordinate as a 0.32
+/// fixed point number.
+#[inline]
+pub fn sample_owen_shuffled_u32(index: u32, dimension: u32, seed: u32) -> u32 {
+    let index = owen_scramble_fast_u32(index, seed);
+    owen_scramble_fast_u32(sobol_u32(index, dimension), dimension_seed(dimension, seed))
+}
+
+/// Same as `sample_owen_fast()` except uses the given Laine-Karras style
+/// hash for the scrambling.  See `owen_scramble_hash_u32()`.
+#[inline]
+pub fn sample_owen_hash<H>(index: u32, dimension: u32, seed: u32, hash: H) -> f32
+where
+    H: Fn(u32, u32) -> u32,
+{
+    u32_to_0_1_f32(owen_scramble_hash_u32(
+        sobol_u32(index, dimension),
+        seed,
+        hash,
+    ))
+}
+
 /// Same as `sample_owen_fast()` except it uses a slower "ground-truth"
 /// implementation of Owen scrambling.
 #[inline]
@@ -42,95 +103,526 @@ fn u32_to_0_1_f32(n: u32) -> f32 {
 }
 
 /// The actual core Sobol samplng code.  Used by the above functions.
-fn sobol_u32(index: u32, dimension: u32) -> u32 {
-    assert!(dimension < MAX_DIMENSION);
-    let vecs = &VECTORS[dimension as usize];
-
-    let mut index = index;
-    let mut result = 0;
-    let mut i = 0;
-    while index != 0 {
-        let j = index.trailing_zeros();
-        result ^= vecs[(i + j) as usize];
-        i += j + 1;
-        index >>= j;
-        index >>= 1;
+///
+/// Returns the coordinate as a 0.32 fixed point number.
+pub fn sobol_u32(index: u32, dimension: u32) -> u32 {
+    match TABLE.get() {
+        Some(table) => table.sobol_u32(index, dimension),
+        None => SobolTable::BUILTIN.sobol_u32(index, dimension),
     }
+}
 
-    result
+/// Same as `sobol_u32()`, but fills `out` with many points at once.  See
+/// `SobolTable::fill_u32()`.
+pub fn fill_u32(start: u32, dimensions: Range<u32>, out: &mut [u32]) {
+    table().fill_u32(start, dimensions, out)
 }
 
-/// Scrambles `n` using fast hash-based Owen scrambling.
+/// Iterates over the points of one dimension in Gray-code order.  See
+/// `SobolTable::gray_code_iter()`.
+pub fn gray_code_iter(dimension: u32, start: u32) -> GrayCodeIter<'static> {
+    table().gray_code_iter(dimension, start)
+}
+
+/// The table of direction vectors used by the free functions in this
+/// module.
 ///
-/// Various hashes are included below, and can be uncommented to try them out.
-pub fn owen_scramble_fast_u32(x: u32, seed: u32) -> u32 {
-    let mut x = x.reverse_bits();
+/// This is the built-in table generated by `build.rs`, unless another one
+/// was set with `set_table()`.
+pub fn table() -> &'static SobolTable {
+    TABLE.get().unwrap_or(&SobolTable::BUILTIN)
+}
 
-    // Randomize the seed value.
-    let seed = hash_u32(seed, 0xa14a177d);
+/// Sets the table of direction vectors used by the free functions in this
+/// module.
+///
+/// This can only be done once, and should be done before any points are
+/// generated.  If the table was already set, the given table is returned as
+/// the error.
+pub fn set_table(table: SobolTable) -> Result<(), SobolTable> {
+    TABLE.set(table)
+}
+
+static TABLE: OnceLock<SobolTable> = OnceLock::new();
+
+/// A table of Sobol direction vectors, which can be loaded at runtime from
+/// any of the Joe-Kuo direction numbers files.
+#[derive(Debug, Clone)]
+pub struct SobolTable {
+    name: Cow<'static, str>,
+    vectors: Cow<'static, [[u32; 32]]>,
+}
+
+impl SobolTable {
+    /// The table generated by `build.rs`.
+    pub const BUILTIN: SobolTable = SobolTable {
+        name: Cow::Borrowed("built-in"),
+        vectors: Cow::Borrowed(VECTORS),
+    };
+
+    /// Generates a table with the first `dimensions` dimensions from the
+    /// text of a Joe-Kuo direction numbers file, or all of the dimensions in
+    /// the file if `dimensions` is `None`.
+    pub fn from_direction_numbers(
+        name: &str,
+        text: &str,
+        dimensions: Option<u32>,
+    ) -> Result<SobolTable, String> {
+        let dimensions = dimensions
+            .map(|d| d as usize)
+            .unwrap_or_else(|| direction_numbers::dimension_count(text));
+        Ok(SobolTable {
+            name: name.to_string().into(),
+            vectors: direction_numbers::generate_direction_vectors(text, dimensions)?.into(),
+        })
+    }
+
+    /// Same as `from_direction_numbers()`, but reads the direction numbers
+    /// from a file.
+    pub fn load(path: &Path, dimensions: Option<u32>) -> Result<SobolTable, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Couldn't read \"{}\": {}", path.display(), e))?;
+        SobolTable::from_direction_numbers(&path.display().to_string(), &text, dimensions)
+            .map_err(|e| format!("\"{}\": {}", path.display(), e))
+    }
+
+    /// Where the table came from.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The number of dimensions in the table.
+    pub fn dimensions(&self) -> u32 {
+        self.vectors.len() as u32
+    }
 
-    // // Original Laine-Karras hash.
-    // x = x.wrapping_add(seed);
-    // x ^= x.wrapping_mul(0x6c50b47c);
-    // x ^= x.wrapping_mul(0xb82f1e52);
-    // x ^= x.wrapping_mul(0xc7afe638);
-    // x ^= x.wrapping_mul(0x8d22f6e6);
+    /// Checks that `dimension` is in the table, returning a descriptive
+    /// error if it isn't.
+    pub fn check_dimension(&self, dimension: u32) -> Result<(), String> {
+        if dimension < self.dimensions() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Sobol dimension {} is out of range: the {} table has {} dimensions, \
+                 so the maximum is {}.",
+                dimension,
+                self.name,
+                self.dimensions(),
+                self.dimensions() - 1
+            ))
+        }
+    }
 
-    // // "Improved" version 2.  Not actually that good.
-    // // From https://psychopath.io/post/2021_01_02_sobol_sampling_take_2
-    // x = x.wrapping_add(seed);
-    // x ^= 0xdc967795;
-    // x = x.wrapping_mul(0x97b754b7);
-    // x ^= 0x866350b1;
-    // x = x.wrapping_mul(0x9e3779cd);
+    /// Same as the module's `sample()`, but using this table.
+    #[inline]
+    pub fn sample(&self, index: u32, dimension: u32) -> f32 {
+        u32_to_0_1_f32(self.sobol_u32(index, dimension))
+    }
 
-    // // Fast, reasonable quality.
-    // x = x.wrapping_add(x << 2);
-    // x ^= x.wrapping_mul(0xfe9b5742);
-    // x = x.wrapping_add(seed);
-    // x = x.wrapping_mul(seed | 1);
+    /// Same as the module's `sobol_u32()`, but using this table.
+    ///
+    /// Panics if `dimension` isn't in the table.  See `try_sobol_u32()`.
+    #[inline]
+    pub fn sobol_u32(&self, index: u32, dimension: u32) -> u32 {
+        self.try_sobol_u32(index, dimension)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
 
-    // // Medium-fast, best quality so far.
-    x = x.wrapping_mul(0x788aeeed);
-    x ^= x.wrapping_mul(0x41506a02);
-    x = x.wrapping_add(seed);
-    x = x.wrapping_mul(seed | 1);
-    x ^= x.wrapping_mul(0x7483dc64);
+    /// Same as `sobol_u32()`, but returns the error from
+    /// `check_dimension()` instead of panicking if `dimension` isn't in
+    /// the table.
+    #[inline]
+    pub fn try_sobol_u32(&self, index: u32, dimension: u32) -> Result<u32, String> {
+        self.check_dimension(dimension)?;
+        let vecs = &self.vectors[dimension as usize];
 
-    // x = exec_hash_slice(
-    //     // Good 2-mul hash.
-    //     &[HashOp::ShlAdd(2), HashOp::MulXor(0xfe9b5742), HashOp::Add(0), HashOp::Mul(0), ],
+        let mut index = index;
+        let mut result = 0;
+        let mut i = 0;
+        while index != 0 {
+            let j = index.trailing_zeros();
+            result ^= vecs[(i + j) as usize];
+            i += j + 1;
+            index >>= j;
+            index >>= 1;
+        }
 
-    //     // // Best quality so far.
-    //     // &[HashOp::Mul(0x788aeeed), HashOp::MulXor(0x41506a02), HashOp::Add(0), HashOp::Mul(0), HashOp::MulXor(0x7483dc64), ],
+        Ok(result)
+    }
+
+    /// Computes consecutive points starting from index `start`, for the
+    /// given range of dimensions.
+    ///
+    /// `out` is filled point by point, with the coordinates of each point
+    /// consecutive, so its length must be a multiple of the number of
+    /// dimensions, and the number of points it holds must fit in the
+    /// indices from `start` up to and including `u32::MAX`.  The results
+    /// are identical to `sobol_u32()`, but after the first point each
+    /// coordinate only takes a single XOR.
+    ///
+    /// Panics if any of the dimensions isn't in the table.  See
+    /// `try_fill_u32()`.
+    pub fn fill_u32(&self, start: u32, dimensions: Range<u32>, out: &mut [u32]) {
+        self.try_fill_u32(start, dimensions, out)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
 
-    //     x,
-    //     seed,
-    // );
+    /// Same as `fill_u32()`, but returns the error from `check_dimension()`
+    /// instead of panicking if any of the dimensions isn't in the table.
+    /// `out` is left untouched then.
+    pub fn try_fill_u32(
+        &self,
+        start: u32,
+        dimensions: Range<u32>,
+        out: &mut [u32],
+    ) -> Result<(), String> {
+        let dimension_count = dimensions.len();
+        if dimension_count == 0 {
+            assert!(out.is_empty());
+            return Ok(());
+        }
+        assert_eq!(out.len() % dimension_count, 0);
+        let point_count = out.len() / dimension_count;
+        assert!(
+            point_count as u64 <= (1u64 << 32) - start as u64,
+            "{} points starting from index {} run past u32::MAX",
+            point_count,
+            start
+        );
+        if let Some(last) = dimensions.clone().last() {
+            self.check_dimension(last)?;
+        }
 
-    x.reverse_bits()
+        // Going from index `i` to `i + 1` flips the lowest zero bit of `i`
+        // and all the ones below it, so the XOR of their direction vectors
+        // is precomputed for each possible lowest zero bit.
+        let prefix_xors: Vec<[u32; 32]> = dimensions
+            .clone()
+            .map(|dimension| {
+                let mut prefix_xors = [0u32; 32];
+                let mut xor = 0;
+                for (p, &v) in prefix_xors
+                    .iter_mut()
+                    .zip(self.vectors[dimension as usize].iter())
+                {
+                    xor ^= v;
+                    *p = xor;
+                }
+                prefix_xors
+            })
+            .collect();
+
+        let mut point: Vec<u32> = dimensions
+            .map(|dimension| self.sobol_u32(start, dimension))
+            .collect();
+        for (k, out_point) in out.chunks_exact_mut(dimension_count).enumerate() {
+            if k != 0 {
+                let bit = (start + k as u32).trailing_zeros() as usize;
+                for (x, p) in point.iter_mut().zip(prefix_xors.iter()) {
+                    *x ^= p[bit];
+                }
+            }
+            out_point.copy_from_slice(&point);
+        }
+        Ok(())
+    }
+
+    /// Iterates over the points of one dimension in Gray-code order, which
+    /// only takes a single XOR per point.
+    ///
+    /// The `n`th item is the point with index `n ^ (n >> 1)`, starting from
+    /// `n = start`.  Items are `(index, coordinate)` pairs, where the
+    /// coordinate is the same as from `sobol_u32()`.  Any aligned
+    /// power-of-two block of the Gray-code order is a reordering of the
+    /// same block of the natural order.
+    ///
+    /// Panics if `dimension` isn't in the table.  See
+    /// `try_gray_code_iter()`.
+    pub fn gray_code_iter(&self, dimension: u32, start: u32) -> GrayCodeIter<'_> {
+        self.try_gray_code_iter(dimension, start)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Same as `gray_code_iter()`, but returns the error from
+    /// `check_dimension()` instead of panicking if `dimension` isn't in
+    /// the table.
+    pub fn try_gray_code_iter(
+        &self,
+        dimension: u32,
+        start: u32,
+    ) -> Result<GrayCodeIter<'_>, String> {
+        Ok(GrayCodeIter {
+            value: self.try_sobol_u32(start ^ (start >> 1), dimension)?,
+            vectors: &self.vectors[dimension as usize],
+            n: Some(start),
+        })
+    }
+}
+
+/// An iterator over the points of one dimension of the Sobol sequence in
+/// Gray-code order.  See `SobolTable::gray_code_iter()`.
+#[derive(Debug, Clone)]
+pub struct GrayCodeIter<'a> {
+    vectors: &'a [u32; 32],
+    n: Option<u32>, // `None` once the end of the sequence is reached.
+    value: u32,
+}
+
+impl<'a> Iterator for GrayCodeIter<'a> {
+    type Item = (u32, u32); // (index, coordinate)
+
+    #[inline]
+    fn next(&mut self) -> Option<(u32, u32)> {
+        let n = self.n?;
+        let item = (n ^ (n >> 1), self.value);
+
+        // The Gray codes of `n` and `n + 1` differ in just the bit of
+        // `n + 1`'s lowest set bit.
+        self.n = n.checked_add(1);
+        if let Some(next) = self.n {
+            self.value ^= self.vectors[next.trailing_zeros() as usize];
+        }
+
+        Some(item)
+    }
+}
+
+/// Derives the scramble seed for one dimension of a point set from the
+/// seed of the whole point set.
+///
+/// The dimension seeds are decorrelated from each other by hashing, so any
+/// of the scrambling functions can be used with them.
+#[inline]
+pub fn dimension_seed(dimension: u32, seed: u32) -> u32 {
+    hash_u32(dimension, seed)
+}
+
+/// The name of the hash in the `hashes` registry that
+/// `owen_scramble_fast_u32()` scrambles with.
+pub const FAST_HASH: &str = "medium";
+
+/// Scrambles `n` using fast hash-based Owen scrambling.
+///
+/// Uses the `FAST_HASH` hash from the `hashes` registry.  See
+/// `owen_scramble_hash_u32()` for using other hashes.
+#[inline]
+pub fn owen_scramble_fast_u32(x: u32, seed: u32) -> u32 {
+    owen_scramble_hash_u32(x, seed, hashes::medium)
+}
+
+/// Scrambles `n` using the given Laine-Karras style hash, such as one of
+/// the hashes in the `hashes` registry.
+#[inline]
+pub fn owen_scramble_hash_u32<H>(x: u32, seed: u32, hash: H) -> u32
+where
+    H: Fn(u32, u32) -> u32, // (input, seed) -> output
+{
+    let x = x.reverse_bits();
+
+    // Randomize the seed value.
+    let seed = hash_u32(seed, 0xa14a177d);
+
+    hash(x, seed).reverse_bits()
 }
 
 /// Same as `owen_scramble_fast_u32()` above, except uses a slower
 /// "ground truth" algorithm for Owen scrambling.
 pub fn owen_scramble_reference_u32(n: u32, seed: u32) -> u32 {
+    owen_scramble_reference(n, seed)
+}
+
+/// The "ground truth" Owen scramble, on any word size.
+pub fn owen_scramble_reference<W: Word>(n: W, seed: W) -> W {
     // A high-quality, seedable hash function.
     // See https://en.wikipedia.org/wiki/SipHash
-    fn siphash(n: u32, seed: u32) -> u32 {
+    fn siphash<W: Word>(n: W, seed: W) -> W {
         use std::hash::Hasher;
-        let mut hasher = siphasher::sip::SipHasher13::new_with_keys(seed as u64, seed as u64);
-        hasher.write_u32(n);
-        hasher.finish() as u32
+        let key = seed.to_u64();
+        let mut hasher = siphasher::sip::SipHasher13::new_with_keys(key, key);
+        n.write_to_hasher(&mut hasher);
+        W::from_u64(hasher.finish())
     }
 
     // The Owen scramble.
     let in_bits = n;
     let mut out_bits = n;
-    for bit in 0..32 {
-        let high_mask = !1 << bit;
+    for bit in 0..W::BITS {
+        let high_mask = !W::ONE << bit;
         let hash = siphash(in_bits & high_mask, seed);
-        out_bits ^= hash & (1 << bit);
+        out_bits = out_bits ^ (hash & (W::ONE << bit));
     }
 
     out_bits
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The Pearson correlation of two equally long lists of values.
+    fn correlation(xs: &[f64], ys: &[f64]) -> f64 {
+        let n = xs.len() as f64;
+        let mean_x = xs.iter().sum::<f64>() / n;
+        let mean_y = ys.iter().sum::<f64>() / n;
+        let mut cov = 0.0;
+        let mut var_x = 0.0;
+        let mut var_y = 0.0;
+        for (x, y) in xs.iter().zip(ys.iter()) {
+            cov += (x - mean_x) * (y - mean_y);
+            var_x += (x - mean_x) * (x - mean_x);
+            var_y += (y - mean_y) * (y - mean_y);
+        }
+        cov / (var_x * var_y).sqrt()
+    }
+
+    #[test]
+    fn dimension_seeds_are_decorrelated() {
+        const SEEDS: u32 = 4096;
+        // Well over four standard deviations of the correlation of
+        // `SEEDS` independent pairs.
+        const MAX_CORRELATION: f64 = 0.07;
+
+        for &index in [0, 1, 5, 1000].iter() {
+            for &(a, b) in [(0, 1), (1, 2), (0, 7), (3, 100)].iter() {
+                let mut xs = Vec::new();
+                let mut ys = Vec::new();
+                let mut shared_xs = Vec::new();
+                let mut shared_ys = Vec::new();
+                for seed in 0..SEEDS {
+                    xs.push(sample_owen(index, a, seed) as f64);
+                    ys.push(sample_owen(index, b, seed) as f64);
+                    shared_xs.push(sample_owen_fast(index, a, seed) as f64);
+                    shared_ys.push(sample_owen_fast(index, b, seed) as f64);
+                }
+                let r = correlation(&xs, &ys);
+                assert!(
+                    r.abs() < MAX_CORRELATION,
+                    "index {}, dimensions {} and {}: correlation {}",
+                    index,
+                    a,
+                    b,
+                    r
+                );
+
+                // The control: with the seed shared by both dimensions the
+                // scrambles are strongly correlated, which the threshold
+                // must catch.  (At index 0 every dimension is 0 before
+                // scrambling, so that proves nothing about the threshold.)
+                if index != 0 {
+                    let shared_r = correlation(&shared_xs, &shared_ys);
+                    assert!(
+                        shared_r.abs() > MAX_CORRELATION,
+                        "index {}, dimensions {} and {}: shared seed correlation {}",
+                        index,
+                        a,
+                        b,
+                        shared_r
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dimension_seeds_are_deterministic() {
+        for &seed in [0, 1, 0xdeadbeef].iter() {
+            for dimension in 0..8 {
+                let d_seed = dimension_seed(dimension, seed);
+                assert_eq!(d_seed, dimension_seed(dimension, seed));
+                for index in 0..64 {
+                    let x = sample_owen(index, dimension, seed);
+                    assert_eq!(x.to_bits(), sample_owen(index, dimension, seed).to_bits());
+                    assert_eq!(
+                        x.to_bits(),
+                        sample_owen_fast(index, dimension, d_seed).to_bits()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn try_variants_report_out_of_range_dimensions() {
+        let table = &SobolTable::BUILTIN;
+        let last = table.dimensions() - 1;
+
+        assert_eq!(table.try_sobol_u32(5, last), Ok(table.sobol_u32(5, last)));
+        assert!(table.try_sobol_u32(5, last + 1).is_err());
+
+        let mut out = [1u32; 4];
+        assert!(table.try_fill_u32(0, last..last + 2, &mut out).is_err());
+        assert_eq!(out, [1; 4]);
+        assert!(table.try_fill_u32(0, last - 1..last + 1, &mut out).is_ok());
+
+        assert!(table.try_gray_code_iter(last, 0).is_ok());
+        assert!(table.try_gray_code_iter(last + 1, 0).is_err());
+    }
+
+    /// Starts that aren't aligned to any power of two, and ones that end
+    /// right at the last index, `u32::MAX`.
+    const STARTS: [(u32, u32); 4] = [
+        (12345, 300),
+        (0xabcd_ef01, 77),
+        (u32::MAX - 99, 100),
+        (u32::MAX, 1),
+    ];
+
+    #[test]
+    fn fill_u32_matches_sobol_u32() {
+        let table = &SobolTable::BUILTIN;
+        for &(start, count) in STARTS.iter() {
+            let end = table.dimensions();
+            for dimensions in [0..1, 0..5, 3..11, end - 4..end].iter() {
+                let mut out = vec![0u32; count as usize * dimensions.len()];
+                table.fill_u32(start, dimensions.clone(), &mut out);
+                for (k, point) in out.chunks_exact(dimensions.len()).enumerate() {
+                    for (dimension, &x) in dimensions.clone().zip(point.iter()) {
+                        let index = start + k as u32;
+                        assert_eq!(
+                            x,
+                            table.sobol_u32(index, dimension),
+                            "index {}, dimension {}",
+                            index,
+                            dimension
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn fill_u32_panics_past_the_last_index() {
+        let mut out = vec![0u32; 2];
+        SobolTable::BUILTIN.fill_u32(u32::MAX, 0..1, &mut out);
+    }
+
+    #[test]
+    fn gray_code_iter_matches_sobol_u32() {
+        let table = &SobolTable::BUILTIN;
+        for &(start, count) in STARTS.iter() {
+            for &dimension in [0, 1, 7, table.dimensions() - 1].iter() {
+                let points = table.gray_code_iter(dimension, start).take(count as usize);
+                for (n, (index, x)) in (start..=u32::MAX).zip(points) {
+                    assert_eq!(index, n ^ (n >> 1));
+                    assert_eq!(
+                        x,
+                        table.sobol_u32(index, dimension),
+                        "n {}, dimension {}",
+                        n,
+                        dimension
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn gray_code_iter_ends_after_the_last_index() {
+        let points: Vec<_> = SobolTable::BUILTIN
+            .gray_code_iter(0, u32::MAX - 2)
+            .collect();
+        assert_eq!(points.len(), 3);
+    }
+}
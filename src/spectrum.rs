@@ -0,0 +1,290 @@
+//! Fourier power spectra of 2D point sets.
+//!
+//! The periodogram of a point set is `|sum_j exp(-2 pi i k . x_j)|^2 / N`
+//! for integer frequencies `k`.  For uniform random points it's 1 at every
+//! frequency but the DC term.  Well-stratified points instead have a dark
+//! region at low frequencies, and any structured artifacts, e.g. from a
+//! weak scramble hash, show up as spikes or lines.
+
+use std::fs::File;
+
+use rayon::prelude::*;
+
+/// An averaged 2D periodogram.
+#[derive(Debug, Clone)]
+pub struct Spectrum {
+    /// The periodogram covers frequencies `-resolution / 2` up to (but not
+    /// including) `resolution / 2` on each axis.
+    pub resolution: usize,
+
+    /// The power at each frequency, row-major with `y` as the row.  The
+    /// zero frequency is at `(resolution / 2, resolution / 2)`.
+    pub power: Vec<f64>,
+}
+
+impl Spectrum {
+    /// The power at frequency `(kx, ky)`.
+    pub fn get(&self, kx: isize, ky: isize) -> f64 {
+        let half = (self.resolution / 2) as isize;
+        self.power[((ky + half) as usize) * self.resolution + (kx + half) as usize]
+    }
+
+    /// The radially averaged power spectrum: the average power over all
+    /// frequencies with a magnitude that rounds to each integer, from 0 up
+    /// to `resolution / 2 - 1`.  The DC term is excluded.
+    pub fn radial_average(&self) -> Vec<f64> {
+        let half = self.resolution / 2;
+        let mut sums = vec![0.0f64; half];
+        let mut counts = vec![0usize; half];
+        for ky in -(half as isize)..(half as isize) {
+            for kx in -(half as isize)..(half as isize) {
+                let r = (((kx * kx + ky * ky) as f64).sqrt()).round() as usize;
+                if r > 0 && r < half {
+                    sums[r] += self.get(kx, ky);
+                    counts[r] += 1;
+                }
+            }
+        }
+        sums.iter()
+            .zip(counts.iter())
+            .map(|(&s, &c)| if c > 0 { s / c as f64 } else { 0.0 })
+            .collect()
+    }
+
+    /// Writes the periodogram as a grayscale png, with a power of 1 as
+    /// mid-gray and 2 or more as white.  The DC term is drawn black.
+    pub fn write_image(&self, file: &mut File) {
+        let res = self.resolution;
+        let mut image = vec![0x00u8; res * res * 4];
+        for y in 0..res {
+            for x in 0..res {
+                let p = if x == res / 2 && y == res / 2 {
+                    0.0
+                } else {
+                    self.power[y * res + x]
+                };
+                let color = ((p * 0.5).clamp(0.0, 1.0) * 255.0) as u8;
+                let i = (y * res + x) * 4;
+                image[i] = color;
+                image[i + 1] = color;
+                image[i + 2] = color;
+                image[i + 3] = 0xff;
+            }
+        }
+        png_encode_mini::write_rgba_from_u8(file, &image, res as u32, res as u32).unwrap();
+    }
+}
+
+/// Computes the periodogram of the 2D projection onto dimensions
+/// `(dim_x, dim_y)` of the first `n` points of `sample`, averaged over
+/// the given seeds.
+///
+/// `seed` is passed to `sample` as-is for both dimensions, so it's up to
+/// the sampler to decorrelate them if needed.  `resolution` must be even,
+/// so that the zero frequency is at the center.
+/// Panics if `n` is 0 or there are no seeds, since there's nothing to
+/// average.
+pub fn power_spectrum<F>(
+    sample: F,
+    n: u32,
+    (dim_x, dim_y): (u32, u32),
+    seeds: &[u32],
+    resolution: usize,
+) -> Spectrum
+where
+    F: Fn(u32, u32, u32) -> f32 + Sync, // (sample_index, dimension, seed) -> coordinate
+{
+    assert!(resolution % 2 == 0);
+    assert!(n > 0, "can't compute a spectrum of 0 points");
+    assert!(!seeds.is_empty(), "can't compute a spectrum with 0 seeds");
+    let half = (resolution / 2) as isize;
+    let frequencies: Vec<f64> = (-half..half).map(|k| k as f64).collect();
+
+    let power = seeds
+        .par_iter()
+        .map(|&seed| {
+            // Sum of exp(-2 pi i k . x) over the points, as (re, im).  The
+            // exponential is separable, so it's computed from 1D factors.
+            let mut sum = vec![(0.0f64, 0.0f64); resolution * resolution];
+            let mut ex = vec![(0.0f64, 0.0f64); resolution];
+            let mut ey = vec![(0.0f64, 0.0f64); resolution];
+            for i in 0..n {
+                let x = sample(i, dim_x, seed) as f64;
+                let y = sample(i, dim_y, seed) as f64;
+                for (e, &k) in ex.iter_mut().zip(frequencies.iter()) {
+                    let a = -2.0 * std::f64::consts::PI * k * x;
+                    *e = (a.cos(), a.sin());
+                }
+                for (e, &k) in ey.iter_mut().zip(frequencies.iter()) {
+                    let a = -2.0 * std::f64::consts::PI * k * y;
+                    *e = (a.cos(), a.sin());
+                }
+                for (row, &(ey_re, ey_im)) in sum.chunks_exact_mut(resolution).zip(ey.iter()) {
+                    for (s, &(ex_re, ex_im)) in row.iter_mut().zip(ex.iter()) {
+                        s.0 += ex_re * ey_re - ex_im * ey_im;
+                        s.1 += ex_re * ey_im + ex_im * ey_re;
+                    }
+                }
+            }
+            sum.iter()
+                .map(|&(re, im)| (re * re + im * im) / n as f64)
+                .collect::<Vec<f64>>()
+        })
+        .reduce(
+            || vec![0.0f64; resolution * resolution],
+            |mut a, b| {
+                for (a, b) in a.iter_mut().zip(b.iter()) {
+                    *a += b;
+                }
+                a
+            },
+        );
+
+    Spectrum {
+        resolution,
+        power: power.iter().map(|p| p / seeds.len() as f64).collect(),
+    }
+}
+
+const RADIAL_WIDTH: usize = 512;
+const RADIAL_HEIGHT: usize = 256;
+
+/// Writes a radially averaged power spectrum as a line plot png, with the
+/// frequency on the x axis and power from 0 to 2 on the y axis.  The power
+/// of uniform random points, 1, is marked with a gray line.
+pub fn write_radial_image(radial: &[f64], file: &mut File) {
+    let image = radial_image(radial);
+    png_encode_mini::write_rgba_from_u8(file, &image, RADIAL_WIDTH as u32, RADIAL_HEIGHT as u32)
+        .unwrap();
+}
+
+/// The rgba pixels of `write_radial_image()`, bottom row first.
+fn radial_image(radial: &[f64]) -> Vec<u8> {
+    const WIDTH: usize = RADIAL_WIDTH;
+    const HEIGHT: usize = RADIAL_HEIGHT;
+    const POINT_RADIUS: isize = 1;
+
+    let mut image = vec![0xffu8; WIDTH * HEIGHT * 4];
+    let mut plot = |x: isize, y: isize, color: u8| {
+        if x >= 0 && y >= 0 && (x as usize) < WIDTH && (y as usize) < HEIGHT {
+            let i = (y as usize * WIDTH + x as usize) * 4;
+            image[i] = color;
+            image[i + 1] = color;
+            image[i + 2] = color;
+        }
+    };
+    // Note that png rows are stored bottom-up.
+    let power_to_y = |p: f64| ((p / 2.0).clamp(0.0, 1.0) * (HEIGHT - 1) as f64) as isize;
+
+    // Reference line.
+    for x in 0..WIDTH {
+        plot(x as isize, power_to_y(1.0), 0xb0);
+    }
+
+    // The spectrum, linearly interpolated between the frequency bins, and
+    // joined up vertically so that it reads as a line.
+    let bins = radial.len().max(2);
+    let mut prev_y = None;
+    for x in 0..WIDTH {
+        let r = x as f64 / (WIDTH - 1) as f64 * (bins - 1) as f64;
+        let i = (r as usize).min(bins - 2);
+        let t = r - i as f64;
+        let value = |i: usize| radial.get(i).copied().unwrap_or(0.0);
+        let y = power_to_y(value(i) * (1.0 - t) + value(i + 1) * t);
+        let (y0, y1) = match prev_y {
+            Some(py) if py < y => (py, y),
+            Some(py) => (y, py),
+            None => (y, y),
+        };
+        for yy in (y0 - POINT_RADIUS)..=(y1 + POINT_RADIUS) {
+            plot(x as isize, yy, 0x00);
+        }
+        prev_y = Some(y);
+    }
+
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Pcg32;
+    use rand::Rng;
+
+    /// Independent uniform random points.
+    fn random_point(i: u32, d: u32, seed: u32) -> f32 {
+        Pcg32::with_stream(((seed as u64) << 32) | i as u64, d as u64).gen()
+    }
+
+    #[test]
+    fn the_dc_term_is_the_point_count() {
+        for &n in &[1, 7, 64] {
+            let spectrum = power_spectrum(random_point, n, (0, 1), &[1, 2, 3], 8);
+            assert!((spectrum.get(0, 0) - n as f64).abs() < 1.0e-9 * n as f64);
+        }
+    }
+
+    #[test]
+    fn uniform_random_points_average_to_one_away_from_dc() {
+        let seeds: Vec<u32> = (0..512).collect();
+        let spectrum = power_spectrum(random_point, 64, (0, 1), &seeds, 16);
+        for ky in -8..8 {
+            for kx in -8..8 {
+                if (kx, ky) != (0, 0) {
+                    let p = spectrum.get(kx, ky);
+                    assert!((p - 1.0).abs() < 0.4, "({}, {}): {}", kx, ky, p);
+                }
+            }
+        }
+        for (r, &p) in spectrum.radial_average().iter().enumerate().skip(1) {
+            assert!((p - 1.0).abs() < 0.15, "radius {}: {}", r, p);
+        }
+    }
+
+    #[test]
+    fn get_and_radial_average_index_by_frequency() {
+        // Power equal to the frequency's rounded magnitude, plus the row
+        // index in the fractional part to check the layout.
+        let resolution = 8;
+        let half = resolution as isize / 2;
+        let mut power = Vec::new();
+        for ky in -half..half {
+            for kx in -half..half {
+                let r = (((kx * kx + ky * ky) as f64).sqrt()).round();
+                power.push(r + (ky + half) as f64 / 100.0);
+            }
+        }
+        let spectrum = Spectrum { resolution, power };
+        assert_eq!(spectrum.get(-half, -half), 6.0);
+        assert_eq!(spectrum.get(0, 0), 0.04);
+        assert_eq!(spectrum.get(1, 3), 3.07);
+
+        let radial = spectrum.radial_average();
+        assert_eq!(radial.len(), half as usize);
+        assert_eq!(radial[0], 0.0);
+        for (r, &p) in radial.iter().enumerate().skip(1) {
+            assert!((p.floor() - r as f64).abs() < 1.0e-9, "radius {}: {}", r, p);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn an_empty_point_set_has_no_spectrum() {
+        power_spectrum(random_point, 0, (0, 1), &[1], 8);
+    }
+
+    #[test]
+    fn radial_images_have_high_power_at_the_top() {
+        let is_black = |image: &[u8], x: usize, y: usize| image[(y * RADIAL_WIDTH + x) * 4] == 0;
+        let x = RADIAL_WIDTH / 2;
+
+        // The first row in memory is the bottom of the image.
+        let low = radial_image(&[0.0; 4]);
+        assert!(is_black(&low, x, 0));
+        assert!(!is_black(&low, x, RADIAL_HEIGHT - 1));
+
+        let high = radial_image(&[2.0; 4]);
+        assert!(is_black(&high, x, RADIAL_HEIGHT - 1));
+        assert!(!is_black(&high, x, 0));
+    }
+}
@@ -1,101 +1,269 @@
-use std::fs::File;
 use std::io::Write;
 
 use rayon::prelude::*;
 
+use crate::rng::Pcg32;
+use crate::word::Word;
+
+/// The largest word size, in bits, that `Stats` can hold.
+pub const MAX_STATS_BITS: usize = 64;
+
+/// A `bits` x `bits` matrix indexed by input bit and then output bit.
+/// Entries at or above `bits` are unused, and left at zero.
+pub type BitMatrix = [[f64; MAX_STATS_BITS]; MAX_STATS_BITS];
+
 #[derive(Debug, Copy, Clone)]
 pub struct Stats {
-    pub avalanche: [[f64; 32]; 32],
-    pub avalanche_avg_bias: [[f64; 32]; 32], // Average avalanche bias over many seeds.
+    pub bits: usize, // Word size of the measured hash.
+    pub avalanche: BitMatrix,
+    pub avalanche_avg_bias: BitMatrix, // Average avalanche bias over many seeds.
     pub tree_bias: [[f64; 32]; 32],
 }
 
 pub const STATS_ZERO: Stats = Stats {
-    avalanche: [[0.0; 32]; 32],
-    avalanche_avg_bias: [[0.0; 32]; 32],
+    bits: 32,
+    avalanche: [[0.0; MAX_STATS_BITS]; MAX_STATS_BITS],
+    avalanche_avg_bias: [[0.0; MAX_STATS_BITS]; MAX_STATS_BITS],
     tree_bias: [[0.0; 32]; 32],
 };
 
+impl Stats {
+    /// All-zero stats for a `bits`-bit hash.
+    pub fn zero(bits: usize) -> Stats {
+        assert!(bits <= MAX_STATS_BITS);
+        Stats { bits, ..STATS_ZERO }
+    }
+
+    /// The stats of the lowest `bits` bits only, for stats of a narrower
+    /// hash measured on wider words.  The tree bias is kept as-is.
+    pub fn truncated(&self, bits: usize) -> Stats {
+        assert!(bits <= self.bits);
+        let mut stats = Stats::zero(bits);
+        for i in 0..bits {
+            stats.avalanche[i][..bits].copy_from_slice(&self.avalanche[i][..bits]);
+            stats.avalanche_avg_bias[i][..bits]
+                .copy_from_slice(&self.avalanche_avg_bias[i][..bits]);
+        }
+        stats.tree_bias = self.tree_bias;
+        stats
+    }
+}
+
+/// How many rounds of avalanche testing `measure_stats_estimate()` passes
+/// to the hash in a single call.
+const HASH_BATCH_ROUNDS: usize = 64;
+
+/// The number of batches the rounds of `measure_stats_estimate()` are
+/// split into for estimating sampling error.
+pub const STATS_BATCHES: usize = 32;
+
+/// The number of rounds each parallel chunk of `measure_stats_estimate()`
+/// runs.  Rounds are rounded up to a multiple of this.
+const SUB_ROUNDS: u32 = 4096;
+
+/// The fewest rounds `measure_stats_estimate()` needs to estimate errors,
+/// which takes at least two batches.  Each chunk of `SUB_ROUNDS` rounds
+/// goes to one batch, so that's one round more than a single chunk.
+pub const MIN_ERROR_ROUNDS: u32 = SUB_ROUNDS + 1;
+
+/// `Stats` along with estimates of their sampling error.
+#[derive(Debug, Clone)]
+pub struct StatsEstimate {
+    pub stats: Stats,
+
+    /// The standard error of each value in `stats`.  All NaN if there
+    /// were too few rounds to estimate errors, see `has_std_error()`.
+    pub std_error: Stats,
+
+    /// `stats` recomputed with each batch of rounds left out in turn,
+    /// for jackknife error estimates of values derived from `stats`.
+    pub jackknife: Vec<Stats>,
+}
+
+impl StatsEstimate {
+    /// Whether there were enough rounds to estimate errors, which needs at
+    /// least two batches.  See `MIN_ERROR_ROUNDS`.
+    pub fn has_std_error(&self) -> bool {
+        self.jackknife.len() >= 2
+    }
+
+    /// Estimates the standard error of a value derived from the stats,
+    /// such as a score, via the delete-one-batch jackknife.
+    ///
+    /// Returns `None` if there were too few rounds to estimate errors.
+    pub fn std_error_of<F>(&self, f: F) -> Option<f64>
+    where
+        F: Fn(&Stats) -> f64,
+    {
+        if !self.has_std_error() {
+            return None;
+        }
+        Some(jackknife_std_error(self.jackknife.iter().map(f)))
+    }
+
+    /// Estimates the standard error of `f(a) - f(b)`.
+    ///
+    /// This is a paired estimate, which is much tighter than combining the
+    /// two individual errors, but it's only valid if `a` and `b` were
+    /// measured with the same `rounds` and `seed`.
+    ///
+    /// Returns `None` if there were too few rounds to estimate errors.
+    pub fn std_error_of_difference<F>(a: &StatsEstimate, b: &StatsEstimate, f: F) -> Option<f64>
+    where
+        F: Fn(&Stats) -> f64,
+    {
+        assert_eq!(a.jackknife.len(), b.jackknife.len());
+        if !a.has_std_error() {
+            return None;
+        }
+        Some(jackknife_std_error(
+            a.jackknife
+                .iter()
+                .zip(b.jackknife.iter())
+                .map(|(sa, sb)| f(sa) - f(sb)),
+        ))
+    }
+}
+
 /// Measures the statistics of the provided hash function.
-pub fn measure_stats<F>(hash: F, rounds: u32, print_progress: bool) -> Stats
+///
+/// Same as `measure_stats_estimate()`, but without the error estimates.
+pub fn measure_stats<W, F>(hash: F, rounds: u32, seed: u64, print_progress: bool) -> Stats
+where
+    W: Word,
+    F: Fn(&[W], W, &mut [W]) + Sync, // (inputs, seed, outputs)
+{
+    measure_stats_estimate(hash, rounds, seed, print_progress).stats
+}
+
+/// Measures the statistics of the provided hash function, along with
+/// their standard errors.
+///
+/// The hash is run on many inputs with the same seed at once, so that
+/// it can be vectorized.  Use `batch_hash()` to adapt a hash that works
+/// on a single value at a time.
+///
+/// All inputs and seeds are drawn from PRNGs derived from `seed`, one PRNG
+/// stream per chunk of rounds, so the results are the same for the same
+/// `seed` regardless of how many threads are used.  (The accumulated
+/// values are all multiples of 0.5, so the summation order doesn't
+/// matter either.)
+///
+/// The rounds are split into `STATS_BATCHES` batches, and the errors are
+/// estimated from the variation between them.  With fewer than
+/// `MIN_ERROR_ROUNDS` rounds there's only one batch, and then there are
+/// no error estimates.
+///
+/// The hash can be on any word size, and the stats are for that size.
+/// Panics if `rounds` is 0, since there's nothing to average.
+pub fn measure_stats_estimate<W, F>(
+    hash: F,
+    rounds: u32,
+    seed: u64,
+    print_progress: bool,
+) -> StatsEstimate
 where
-    F: Fn(u32, u32) -> u32 + Sync, // (input, seed) -> output
+    W: Word,
+    F: Fn(&[W], W, &mut [W]) + Sync, // (inputs, seed, outputs)
 {
+    assert!(rounds > 0, "can't measure stats with 0 rounds");
+    let bits = W::BITS as usize;
+
     // Break up the rounds into chunks that we can hoist off to different
     // threads.
-    let sub_rounds = 4096;
+    let sub_rounds = SUB_ROUNDS;
     let loop_rounds = (rounds / sub_rounds) + ((rounds % sub_rounds) != 0) as u32;
-    let rounds = loop_rounds * sub_rounds;
+    let batches = STATS_BATCHES.min(loop_rounds as usize);
 
     if print_progress {
         print!("Progress..");
-        std::io::stdout().flush();
+        std::io::stdout().flush().unwrap();
     }
-    let data = (0..loop_rounds)
+    let batch_data = (0..loop_rounds)
         .into_par_iter()
         .map(|lr| {
             if print_progress && (lr % (loop_rounds / 53).max(1)) == 0 {
                 let stdout = std::io::stdout();
                 let mut out = stdout.lock();
-                out.write_all(b".");
-                out.flush();
+                out.write_all(b".").unwrap();
+                out.flush().unwrap();
             }
 
             // Run tests and collect data.
-            let seed = rand::random::<u32>();
-            let mut data = STATS_ZERO;
-            for i in 0..sub_rounds {
-                // Avalanche and avalanche bias.
-                let input_1 = rand::random::<u32>();
-                let output_1 = hash(input_1, seed);
-                for bit_in in 0..32 {
-                    let input_2 = input_1 ^ (1 << bit_in);
-                    let output_2 = hash(input_2, seed);
-                    let diff_1 = output_1 ^ output_2;
-                    for bit_out in 0..32 {
-                        if (diff_1 & (1 << bit_out)) != 0 {
-                            data.avalanche[bit_in][bit_out] += 1.0;
-                            data.avalanche_avg_bias[bit_in][bit_out] += 1.0;
+            let bits = W::BITS as usize; // Compile-time constant, unlike the captured one.
+            let mut rng = Pcg32::with_stream(seed, lr as u64);
+            let seed = W::random(&mut rng);
+            let mut data = Stats::zero(bits);
+
+            // Avalanche and avalanche bias.
+            //
+            // The hash is run on `HASH_BATCH_ROUNDS` rounds worth of inputs
+            // at a time: each original input followed by its single-bit
+            // flips.  The flipped output bits are counted as integers, which
+            // is much faster than counting into the floating point stats.
+            let mut inputs = vec![W::ZERO; HASH_BATCH_ROUNDS * (bits + 1)];
+            let mut outputs = vec![W::ZERO; HASH_BATCH_ROUNDS * (bits + 1)];
+            let mut counts = [[0u32; MAX_STATS_BITS]; MAX_STATS_BITS];
+            for _ in 0..(sub_rounds as usize / HASH_BATCH_ROUNDS) {
+                for round in inputs.chunks_exact_mut(bits + 1) {
+                    let input_1 = W::random(&mut rng);
+                    round[0] = input_1;
+                    for bit_in in 0..bits {
+                        round[bit_in + 1] = input_1 ^ (W::ONE << bit_in as u32);
+                    }
+                }
+                hash(&inputs, seed, &mut outputs);
+                for round in outputs.chunks_exact(bits + 1) {
+                    let output_1 = round[0];
+                    for (row, &output_2) in counts.iter_mut().zip(round[1..].iter()) {
+                        let diff_1 = output_1 ^ output_2;
+                        for (bit_out, count) in row[..bits].iter_mut().enumerate() {
+                            *count += ((diff_1 >> bit_out as u32) & W::ONE).to_u64() as u32;
                         }
                     }
                 }
-
-                // Tree seeding bias.
-                let seed2 = rand::random::<u32>();
-                let input_3 = rand::random::<u32>();
-                let output_3 = hash(input_3, seed2);
-                let input_4 = rand::random::<u32>();
-                let output_4 = hash(input_4, seed2);
-                let mut x = output_3 ^ output_4;
-                let mut y = input_3 ^ input_4;
-                while x & 1 == 0 && y & 1 == 0 && (x != 0 || y != 0) {
-                    x >>= 1;
-                    y >>= 1;
+            }
+            for i in 0..bits {
+                for j in 0..bits {
+                    data.avalanche[i][j] = counts[i][j] as f64;
+                    data.avalanche_avg_bias[i][j] =
+                        (counts[i][j] as f64 - (0.5 * sub_rounds as f64)).abs();
                 }
-                y = y.reverse_bits() >> 26;
-                x = x.reverse_bits() >> 26;
-                data.tree_bias[x as usize & 0b11111][y as usize & 0b11111] += 0.5;
             }
 
-            // Process data.
-            for i in 0..32 {
-                for j in 0..32 {
-                    data.avalanche_avg_bias[i][j] =
-                        (data.avalanche_avg_bias[i][j] - (0.5 * sub_rounds as f64)).abs();
+            // Tree seeding bias.
+            for _ in 0..sub_rounds {
+                let seed2 = W::random(&mut rng);
+                let inputs = [W::random(&mut rng), W::random(&mut rng)];
+                let mut outputs = [W::ZERO; 2];
+                hash(&inputs, seed2, &mut outputs);
+                let mut x = outputs[0] ^ outputs[1];
+                let mut y = inputs[0] ^ inputs[1];
+                while !x.bit(0) && !y.bit(0) && (x != W::ZERO || y != W::ZERO) {
+                    x = x >> 1;
+                    y = y >> 1;
                 }
+                let y = (y.reverse_bits() >> (W::BITS - 6)).to_u64() as usize;
+                let x = (x.reverse_bits() >> (W::BITS - 6)).to_u64() as usize;
+                data.tree_bias[x & 0b11111][y & 0b11111] += 0.5;
             }
 
-            data
+            (lr as usize % batches, data)
         })
+        .fold(
+            || vec![(0, Stats::zero(bits)); batches],
+            |mut acc, (batch, data)| {
+                acc[batch].0 += 1;
+                add_stats(&mut acc[batch].1, &data);
+                acc
+            },
+        )
         .reduce(
-            || STATS_ZERO,
+            || vec![(0, Stats::zero(bits)); batches],
             |mut a, b| {
-                for i in 0..32 {
-                    for j in 0..32 {
-                        a.avalanche[i][j] += b.avalanche[i][j];
-                        a.avalanche_avg_bias[i][j] += b.avalanche_avg_bias[i][j];
-                        a.tree_bias[i][j] += b.tree_bias[i][j];
-                    }
+                for (a, b) in a.iter_mut().zip(b.iter()) {
+                    a.0 += b.0;
+                    add_stats(&mut a.1, &b.1);
                 }
                 a
             },
@@ -106,73 +274,280 @@ where
         );
     }
 
-    let mut stats = STATS_ZERO;
+    // Compute the stats, and the leave-one-batch-out stats.
+    let mut data = Stats::zero(bits);
+    for (_, batch) in batch_data.iter() {
+        add_stats(&mut data, batch);
+    }
+    let stats = normalize_stats(&data, loop_rounds * sub_rounds);
+    let jackknife: Vec<Stats> = batch_data
+        .iter()
+        .map(|(chunks, batch)| {
+            let mut rest = data;
+            for i in 0..bits {
+                for j in 0..bits {
+                    rest.avalanche[i][j] -= batch.avalanche[i][j];
+                    rest.avalanche_avg_bias[i][j] -= batch.avalanche_avg_bias[i][j];
+                }
+            }
+            for i in 0..32 {
+                for j in 0..32 {
+                    rest.tree_bias[i][j] -= batch.tree_bias[i][j];
+                }
+            }
+            normalize_stats(&rest, (loop_rounds - chunks) * sub_rounds)
+        })
+        .collect();
+
+    // Standard errors of the individual stats.
+    let mut std_error = Stats::zero(bits);
+    for i in 0..bits {
+        for j in 0..bits {
+            std_error.avalanche[i][j] =
+                jackknife_std_error(jackknife.iter().map(|s| s.avalanche[i][j]));
+            std_error.avalanche_avg_bias[i][j] =
+                jackknife_std_error(jackknife.iter().map(|s| s.avalanche_avg_bias[i][j]));
+        }
+    }
     for i in 0..32 {
         for j in 0..32 {
+            std_error.tree_bias[i][j] =
+                jackknife_std_error(jackknife.iter().map(|s| s.tree_bias[i][j]));
+        }
+    }
+
+    StatsEstimate {
+        stats,
+        std_error,
+        jackknife,
+    }
+}
+
+/// Runs a single-value hash on a batch of inputs.  Useful for adapting
+/// single-value hashes for `measure_stats()`.
+#[inline(always)]
+pub fn batch_hash<W, F>(hash: F, inputs: &[W], seed: W, outputs: &mut [W])
+where
+    W: Word,
+    F: Fn(W, W) -> W, // (input, seed) -> output
+{
+    for (out, &n) in outputs.iter_mut().zip(inputs.iter()) {
+        *out = hash(n, seed);
+    }
+}
+
+fn add_stats(a: &mut Stats, b: &Stats) {
+    assert_eq!(a.bits, b.bits);
+    for i in 0..a.bits {
+        for j in 0..a.bits {
+            a.avalanche[i][j] += b.avalanche[i][j];
+            a.avalanche_avg_bias[i][j] += b.avalanche_avg_bias[i][j];
+        }
+    }
+    for i in 0..32 {
+        for j in 0..32 {
+            a.tree_bias[i][j] += b.tree_bias[i][j];
+        }
+    }
+}
+
+/// Turns the raw accumulated data of `measure_stats_estimate()` into
+/// stats.
+fn normalize_stats(data: &Stats, rounds: u32) -> Stats {
+    let mut stats = Stats::zero(data.bits);
+    for i in 0..data.bits {
+        for j in 0..data.bits {
             stats.avalanche[i][j] += data.avalanche[i][j] / rounds as f64;
             stats.avalanche_avg_bias[i][j] += data.avalanche_avg_bias[i][j] * 2.0 / rounds as f64;
+        }
+    }
+    for i in 0..32 {
+        for j in 0..32 {
             stats.tree_bias[i][j] += data.tree_bias[i][j] / rounds as f64 * 32.0 * 32.0;
         }
     }
-
     stats
 }
 
-pub fn print_stats(stats: Stats) {
-    // Calculate reduced stats
-    let mut reduced_stats = [0.0f64; 32]; // (avg, max)
-    for bit_in in 0..32 {
-        for bit_out in (bit_in + 1)..32 {
+/// Computes the jackknife standard error from the leave-one-out values of
+/// an estimate.  Returns NaN if there are fewer than two values.
+fn jackknife_std_error<I: Iterator<Item = f64>>(leave_one_out: I) -> f64 {
+    let values: Vec<f64> = leave_one_out.collect();
+    let n = values.len() as f64;
+    if values.len() < 2 {
+        return f64::NAN;
+    }
+    let mean = values.iter().sum::<f64>() / n;
+    let sum_sq: f64 = values.iter().map(|v| (v - mean) * (v - mean)).sum();
+    ((n - 1.0) / n * sum_sq).sqrt()
+}
+
+/// The cumulative distribution function of the standard normal
+/// distribution.
+///
+/// Uses the erfc approximation from Numerical Recipes, which has a
+/// fractional error of less than 1.2e-7 everywhere.
+pub fn normal_cdf(x: f64) -> f64 {
+    let z = x.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let erfc = t
+        * (-z * z - 1.26551223
+            + t * (1.00002368
+                + t * (0.37409196
+                    + t * (0.09678418
+                        + t * (-0.18628806
+                            + t * (0.27886807
+                                + t * (-1.13520398
+                                    + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277)))))))))
+            .exp();
+    if x >= 0.0 {
+        1.0 - 0.5 * erfc
+    } else {
+        0.5 * erfc
+    }
+}
+
+/// The average avalanche bias of each output bit, over the input bits
+/// below it.
+pub fn per_output_bit_bias(stats: &Stats) -> Vec<f64> {
+    let mut reduced_stats = vec![0.0f64; stats.bits];
+    for bit_in in 0..stats.bits {
+        for bit_out in (bit_in + 1)..stats.bits {
             reduced_stats[bit_out] += stats.avalanche_avg_bias[bit_in][bit_out] / bit_out as f64;
         }
     }
+    reduced_stats
+}
 
-    // Calculate average bias.
+/// The average avalanche bias over all input bits and the output bits
+/// above them.
+pub fn total_avg_bias(stats: &Stats) -> f64 {
     let mut avg_bias = 0.0;
-    for bit_in in 0..32 {
-        for bit_out in (bit_in + 1)..32 {
+    for bit_in in 0..stats.bits {
+        for bit_out in (bit_in + 1)..stats.bits {
             avg_bias += stats.avalanche_avg_bias[bit_in][bit_out];
         }
     }
-    avg_bias /= (32 * 31 / 2) as f64;
+    avg_bias / (stats.bits * (stats.bits - 1) / 2) as f64
+}
 
+pub fn print_stats(stats: &Stats) {
     // Print info.
-    println!("Per-output-bit average bias:\n{:0.2?}", reduced_stats);
-    println!("Total average bias:\n{:0.3}", avg_bias);
-}
-
-pub fn write_stats_image(stats: Stats, file: &mut File) {
-    const BIT_PIXEL_SIZE: usize = 8;
-    const WIDTH: usize = BIT_PIXEL_SIZE * 32 * 3;
-    const HEIGHT: usize = BIT_PIXEL_SIZE * 32;
-    let mut image = vec![0x00u8; 4 * WIDTH * HEIGHT];
-    let mut plot = |x: usize, y: usize, color: u8| {
-        let min_x = x * BIT_PIXEL_SIZE;
-        let min_y = y * BIT_PIXEL_SIZE;
-        let max_x = min_x + BIT_PIXEL_SIZE;
-        let max_y = min_y + BIT_PIXEL_SIZE;
-
-        for y in min_y..max_y {
-            for x in min_x..max_x {
-                image[(y * WIDTH + x) * 4] = color;
-                image[(y * WIDTH + x) * 4 + 1] = color;
-                image[(y * WIDTH + x) * 4 + 2] = color;
-                image[(y * WIDTH + x) * 4 + 3] = 0xFF;
-            }
+    println!(
+        "Per-output-bit average bias:\n{:0.2?}",
+        per_output_bit_bias(stats)
+    );
+    println!("Total average bias:\n{:0.3}", total_avg_bias(stats));
+}
+
+/// Same as `print_stats()`, but also prints the standard errors.
+pub fn print_stats_estimate(estimate: &StatsEstimate) {
+    print_stats(&estimate.stats);
+    if !estimate.has_std_error() {
+        println!(
+            "Standard errors unavailable: needs at least {} rounds.",
+            MIN_ERROR_ROUNDS
+        );
+        return;
+    }
+
+    let mut reduced_error = vec![0.0f64; estimate.stats.bits];
+    for bit_out in 0..estimate.stats.bits {
+        reduced_error[bit_out] = estimate
+            .std_error_of(|s| per_output_bit_bias(s)[bit_out])
+            .unwrap();
+    }
+    println!(
+        "Per-output-bit average bias standard error:\n{:0.4?}",
+        reduced_error
+    );
+    println!(
+        "Total average bias standard error:\n{:0.5}",
+        estimate.std_error_of(total_avg_bias).unwrap()
+    );
+}
+
+/// The average avalanche bias that a proper full Owen scramble has for
+/// output bit `bit_out`, from any input bit below it.
+///
+/// The first sixteen values here were computed analytically, and the
+/// remaining were approximated following a strong trend in the values by
+/// that point, and should be "reasonably" accurate.  Past 32 bits the same
+/// trend (a factor of 1/sqrt(2) per bit) is continued.
+pub fn target_bias(bit_out: usize) -> f64 {
+    const TARGET_BIAS: [f64; 32] = [
+        0.0, 1.0, 0.5, 0.375, 0.273437, 0.19638, 0.139949, 0.099346, 0.070386, 0.049819, 0.035244,
+        0.024927, 0.017628, 0.012466, 0.008815, 0.006233, 0.004407, 0.003117, 0.002204, 0.001558,
+        0.001102, 0.000779, 0.000551, 0.000390, 0.000275, 0.000195, 0.000138, 0.000097, 0.000069,
+        0.000049, 0.000034, 0.000024,
+    ];
+    if bit_out < 32 {
+        TARGET_BIAS[bit_out]
+    } else {
+        TARGET_BIAS[31] * std::f64::consts::FRAC_1_SQRT_2.powi(bit_out as i32 - 31)
+    }
+}
+
+/// How much each output bit contributes to `score_stats()`.
+///
+/// Lower is better, like the score itself.
+pub fn per_output_bit_score(stats: &Stats) -> Vec<f64> {
+    let mut scores = vec![0.0f64; stats.bits];
+    for bit_out in 0..stats.bits {
+        for bit_in in 0..bit_out {
+            // Avalanche metric.
+            let diff = stats.avalanche[bit_in][bit_out] - 0.5;
+            scores[bit_out] += diff * diff;
+
+            // Avalanche bias metric, trying to match the expected bias of a
+            // proper full Owen scramble.
+            let diff = stats.avalanche_avg_bias[bit_in][bit_out] - target_bias(bit_out);
+            scores[bit_out] += diff * diff;
         }
-    };
-
-    for bit_in in 0..32 {
-        for bit_out in 0..32 {
-            let color_avalanche =
-                (stats.avalanche[bit_in][bit_out].min(1.0).max(0.0) * 255.0) as u8;
-            let color_avalanche_bias =
-                (stats.avalanche_avg_bias[bit_in][bit_out].min(1.0).max(0.0) * 255.0) as u8;
-            let color_tree = (stats.tree_bias[bit_in][bit_out].min(1.0).max(0.0) * 255.0) as u8;
-            plot(bit_out, bit_in, color_avalanche);
-            plot(bit_out + 32, bit_in, color_avalanche_bias);
-            plot(bit_out + 64, bit_in, color_tree);
+    }
+    scores
+}
+
+/// Scores the given hash statistics.  Used for searching for better hashes.
+///
+/// Lower score is better (like golf!).
+pub fn score_stats(stats: &Stats) -> f64 {
+    score_stats_bits(stats, stats.bits)
+}
+
+/// Same as `score_stats()`, but only scores the lowest `bits` input and
+/// output bits.  Used for reduced bit-width hashes.
+pub fn score_stats_bits(stats: &Stats, bits: usize) -> f64 {
+    let score: f64 = per_output_bit_score(stats)[..bits].iter().sum();
+
+    // // Tree bias metric
+    // // With the new seed mixing op, this is unnecessary to target since
+    // // it's pretty much always perfect.
+    // for x in 0..32 {
+    //     for y in (x + 1)..32 {
+    //         let diff = (stats.tree_bias[x][y] - 0.5) * 2.0;
+    //         score += diff * diff;
+    //     }
+    // }
+
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash_batch(inputs: &[u32], seed: u32, outputs: &mut [u32]) {
+        for (x, out) in inputs.iter().zip(outputs.iter_mut()) {
+            *out = crate::hash_u32(*x, seed);
         }
     }
-    png_encode_mini::write_rgba_from_u8(file, &image, WIDTH as u32, HEIGHT as u32);
+
+    #[test]
+    fn std_errors_start_at_min_error_rounds() {
+        assert!(measure_stats_estimate(hash_batch, MIN_ERROR_ROUNDS, 1, false).has_std_error());
+        assert!(
+            !measure_stats_estimate(hash_batch, MIN_ERROR_ROUNDS - 1, 1, false).has_std_error()
+        );
+    }
 }
@@ -0,0 +1,214 @@
+//! Per-output-bit comparisons of two `Stats`.
+//!
+//! Each output bit's contribution to `score_stats()` is compared, to show
+//! which bits a change to a hash made better or worse.  If both stats were
+//! measured with `measure_stats_estimate()` with the same rounds and seed,
+//! the differences are tested for significance at the 5% level with paired
+//! error estimates.  Otherwise every difference counts.
+
+use crate::stats::{per_output_bit_score, score_stats, Stats, StatsEstimate};
+
+/// Whether an output bit got better or worse from A to B.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// No significant difference, or no difference at all without error
+    /// estimates.
+    Same,
+
+    /// B has a lower score.
+    Better,
+
+    /// B has a higher score.
+    Worse,
+}
+
+/// The comparison of one output bit.  See `per_output_bit_score()`.
+#[derive(Debug, Copy, Clone)]
+pub struct BitDiff {
+    pub bit: usize,
+    pub score_a: f64,
+    pub score_b: f64,
+
+    /// The paired standard error of `score_b - score_a`, if there are
+    /// error estimates.
+    pub error: Option<f64>,
+
+    pub verdict: Verdict,
+}
+
+impl BitDiff {
+    pub fn difference(&self) -> f64 {
+        self.score_b - self.score_a
+    }
+}
+
+/// The comparison of two stats.  See `diff_stats()`.
+#[derive(Debug, Clone)]
+pub struct StatsDiff {
+    pub score_a: f64,
+    pub score_b: f64,
+
+    /// The paired standard error of `score_b - score_a`, if there are
+    /// error estimates.
+    pub score_error: Option<f64>,
+
+    /// Every output bit but bit 0, which has no input bits below it, so it
+    /// always scores zero.
+    pub bits: Vec<BitDiff>,
+
+    /// Whether the verdicts are from significance tests.
+    pub has_std_error: bool,
+}
+
+impl StatsDiff {
+    /// The output bits with the given verdict.
+    pub fn bits_with(&self, verdict: Verdict) -> Vec<usize> {
+        self.bits
+            .iter()
+            .filter(|b| b.verdict == verdict)
+            .map(|b| b.bit)
+            .collect()
+    }
+}
+
+/// Compares the stats `a` and `b` output bit by output bit.
+///
+/// `estimates` are the estimates the stats are from, if both were
+/// measured.  Their errors are only used if there were enough rounds to
+/// estimate them, see `StatsEstimate::has_std_error()`.  Returns an error
+/// if the stats are for different word sizes.
+pub fn diff_stats(
+    a: &Stats,
+    b: &Stats,
+    estimates: Option<(&StatsEstimate, &StatsEstimate)>,
+) -> Result<StatsDiff, String> {
+    if a.bits != b.bits {
+        return Err(format!(
+            "Can't compare {}-bit stats with {}-bit stats.",
+            a.bits, b.bits
+        ));
+    }
+    let estimates = estimates.filter(|(ea, _)| ea.has_std_error());
+
+    // The paired standard error of `f(b) - f(a)`, if both were measured.
+    let diff_error = |f: &dyn Fn(&Stats) -> f64| {
+        estimates.and_then(|(ea, eb)| StatsEstimate::std_error_of_difference(eb, ea, f))
+    };
+
+    let bit_scores_a = per_output_bit_score(a);
+    let bit_scores_b = per_output_bit_score(b);
+    let bits = (1..a.bits)
+        .map(|bit| {
+            let diff = bit_scores_b[bit] - bit_scores_a[bit];
+            let error = diff_error(&|s: &Stats| per_output_bit_score(s)[bit]);
+            let significant = match error {
+                Some(error) => diff.abs() > 1.96 * error,
+                None => diff != 0.0,
+            };
+            let verdict = if !significant {
+                Verdict::Same
+            } else if diff < 0.0 {
+                Verdict::Better
+            } else {
+                Verdict::Worse
+            };
+            BitDiff {
+                bit,
+                score_a: bit_scores_a[bit],
+                score_b: bit_scores_b[bit],
+                error,
+                verdict,
+            }
+        })
+        .collect();
+
+    Ok(StatsDiff {
+        score_a: score_stats(a),
+        score_b: score_stats(b),
+        score_error: diff_error(&score_stats),
+        bits,
+        has_std_error: estimates.is_some(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashes::{self, SelectedHash};
+    use crate::stats::{measure_stats_estimate, MIN_ERROR_ROUNDS};
+
+    fn estimate(name: &str, rounds: u32) -> StatsEstimate {
+        let hash = SelectedHash::Registered(hashes::get::<u32>(name).unwrap());
+        measure_stats_estimate(hash.batch_hasher(), rounds, 4, false)
+    }
+
+    #[test]
+    fn diff_without_estimates_counts_every_difference() {
+        let a = Stats::zero(8);
+        let mut b = Stats::zero(8);
+        b.avalanche[0][3] = 0.5;
+        b.avalanche_avg_bias[1][5] = 1.0;
+
+        let diff = diff_stats(&a, &b, None).unwrap();
+        assert!(!diff.has_std_error);
+        assert!(diff.score_error.is_none());
+        assert_eq!(diff.bits.len(), 7);
+        assert_eq!(diff.bits_with(Verdict::Better), vec![3]);
+        assert_eq!(diff.bits_with(Verdict::Worse), vec![5]);
+        assert_eq!(diff.bits_with(Verdict::Same), vec![1, 2, 4, 6, 7]);
+        let bit_diffs = diff.bits[2].difference() + diff.bits[4].difference();
+        assert!((diff.score_b - diff.score_a - bit_diffs).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn diff_of_different_word_sizes_is_an_error() {
+        assert!(diff_stats(&Stats::zero(8), &Stats::zero(16), None).is_err());
+    }
+
+    #[test]
+    fn diff_with_estimates_tests_significance() {
+        let a = estimate("laine_karras", 1 << 14);
+        let b = estimate("reference", 1 << 14);
+        let diff = diff_stats(&a.stats, &b.stats, Some((&a, &b))).unwrap();
+        assert!(diff.has_std_error);
+        assert!(diff.score_error.unwrap() > 0.0);
+        for bit in diff.bits.iter() {
+            let error = StatsEstimate::std_error_of_difference(&b, &a, |s| {
+                per_output_bit_score(s)[bit.bit]
+            });
+            assert_eq!(bit.error, error);
+            let z = bit.difference() / error.unwrap();
+            let expected = if z < -1.96 {
+                Verdict::Better
+            } else if z > 1.96 {
+                Verdict::Worse
+            } else {
+                Verdict::Same
+            };
+            assert_eq!(bit.verdict, expected, "bit {}: z = {}", bit.bit, z);
+        }
+        // The reference scramble is much better at the high bits.
+        assert!(diff.bits_with(Verdict::Better).contains(&31));
+
+        // With itself, nothing is significant.
+        let diff = diff_stats(&a.stats, &a.stats, Some((&a, &a))).unwrap();
+        assert_eq!(diff.bits_with(Verdict::Same).len(), diff.bits.len());
+    }
+
+    #[test]
+    fn diff_with_too_few_rounds_counts_every_difference() {
+        let a = estimate("laine_karras", MIN_ERROR_ROUNDS - 1);
+        let b = estimate("reference", MIN_ERROR_ROUNDS - 1);
+        let diff = diff_stats(&a.stats, &b.stats, Some((&a, &b))).unwrap();
+        assert!(!diff.has_std_error);
+        assert!(diff.bits.iter().all(|bit| bit.error.is_none()));
+        assert_eq!(
+            diff.bits_with(Verdict::Same),
+            diff.bits
+                .iter()
+                .filter(|bit| bit.difference() == 0.0)
+                .map(|bit| bit.bit)
+                .collect::<Vec<_>>()
+        );
+    }
+}
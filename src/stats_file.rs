@@ -0,0 +1,559 @@
+//! Reading and writing `Stats` as JSON and CSV, for plotting and for
+//! comparing stored results later.
+//!
+//! Both formats hold the `bits` x `bits` avalanche and avalanche bias
+//! matrices and the 32 x 32 tree bias matrix, indexed `[bit_in][bit_out]`,
+//! along with the derived per-output-bit bias, total average bias and
+//! `score_stats()` score.  The derived values are only for the convenience
+//! of other tools: they're recomputed from the matrices when loading.
+//!
+//! The JSON is a single object:
+//!
+//! ```text
+//! {
+//!   "bits": 32,
+//!   "score": 0.0123,
+//!   "total_avg_bias": 0.0456,
+//!   "per_output_bit_bias": [...],
+//!   "avalanche": [[...], ...],
+//!   "avalanche_avg_bias": [[...], ...],
+//!   "tree_bias": [[...], ...]
+//! }
+//! ```
+//!
+//! The CSV is in long format, with the header `name,bit_in,bit_out,value`
+//! and one row per value.  Matrix entries fill in both bit columns,
+//! per-output-bit values only `bit_out`, and single values neither.
+//!
+//! Floats are written with enough precision to round-trip exactly.
+
+use std::io::Write;
+use std::path::Path;
+
+use crate::stats::{per_output_bit_bias, score_stats, total_avg_bias, Stats, MAX_STATS_BITS};
+
+/// Writes stats to `path`, as JSON or CSV depending on its extension.
+pub fn write_stats_file(stats: &Stats, path: &Path) -> Result<(), String> {
+    let write = match extension(path) {
+        Some("json") => write_stats_json,
+        Some("csv") => write_stats_csv,
+        _ => return Err(unknown_format(path)),
+    };
+    let err = |e: std::io::Error| format!("couldn't write \"{}\": {}", path.display(), e);
+    let mut f = std::io::BufWriter::new(std::fs::File::create(path).map_err(err)?);
+    write(stats, &mut f).and_then(|_| f.flush()).map_err(err)
+}
+
+/// Reads stats written by `write_stats_file()`, as JSON or CSV depending on
+/// the extension of `path`.
+pub fn read_stats_file(path: &Path) -> Result<Stats, String> {
+    let parse = match extension(path) {
+        Some("json") => parse_stats_json,
+        Some("csv") => parse_stats_csv,
+        _ => return Err(unknown_format(path)),
+    };
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("couldn't read \"{}\": {}", path.display(), e))?;
+    parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Writes stats as JSON.
+pub fn write_stats_json<Wr: Write>(stats: &Stats, out: &mut Wr) -> std::io::Result<()> {
+    let bits = stats.bits;
+    writeln!(out, "{{")?;
+    writeln!(out, "  \"bits\": {},", bits)?;
+    writeln!(out, "  \"score\": {},", json_number(score_stats(stats)))?;
+    writeln!(
+        out,
+        "  \"total_avg_bias\": {},",
+        json_number(total_avg_bias(stats))
+    )?;
+    writeln!(
+        out,
+        "  \"per_output_bit_bias\": {},",
+        json_array(&per_output_bit_bias(stats))
+    )?;
+    for (name, matrix, size) in matrices(stats) {
+        writeln!(out, "  \"{}\": [", name)?;
+        for bit_in in 0..size {
+            let comma = if bit_in + 1 < size { "," } else { "" };
+            writeln!(out, "    {}{}", json_array(&matrix[bit_in][..size]), comma)?;
+        }
+        let comma = if name != "tree_bias" { "," } else { "" };
+        writeln!(out, "  ]{}", comma)?;
+    }
+    writeln!(out, "}}")
+}
+
+/// Writes stats as CSV.
+pub fn write_stats_csv<Wr: Write>(stats: &Stats, out: &mut Wr) -> std::io::Result<()> {
+    writeln!(out, "name,bit_in,bit_out,value")?;
+    writeln!(out, "bits,,,{}", stats.bits)?;
+    writeln!(out, "score,,,{:?}", score_stats(stats))?;
+    writeln!(out, "total_avg_bias,,,{:?}", total_avg_bias(stats))?;
+    for (bit_out, v) in per_output_bit_bias(stats).iter().enumerate() {
+        writeln!(out, "per_output_bit_bias,,{},{:?}", bit_out, v)?;
+    }
+    for (name, matrix, size) in matrices(stats) {
+        for bit_in in 0..size {
+            for bit_out in 0..size {
+                writeln!(
+                    out,
+                    "{},{},{},{:?}",
+                    name, bit_in, bit_out, matrix[bit_in][bit_out]
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parses stats written by `write_stats_json()`.
+pub fn parse_stats_json(text: &str) -> Result<Stats, String> {
+    let mut parser = JsonParser { text, pos: 0 };
+    let value = parser.parse_value()?;
+    parser.skip_whitespace();
+    if parser.pos < text.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    let fields = match value {
+        Json::Object(fields) => fields,
+        _ => return Err("expected a JSON object".into()),
+    };
+    let field = |name: &str| {
+        fields
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+            .ok_or_else(|| format!("missing \"{}\"", name))
+    };
+
+    let bits = match field("bits")? {
+        Json::Number(n) if n.fract() == 0.0 => *n as usize,
+        _ => return Err("\"bits\" must be an integer".into()),
+    };
+    let mut stats = new_stats(bits)?;
+    for name in MATRIX_NAMES.iter() {
+        let size = matrix_size(name, bits);
+        let rows = match field(name)? {
+            Json::Array(rows) if rows.len() == size => rows,
+            _ => return Err(format!("\"{}\" must be a {1}x{1} array", name, size)),
+        };
+        for (bit_in, row) in rows.iter().enumerate() {
+            let row = match row {
+                Json::Array(row) if row.len() == size => row,
+                _ => return Err(format!("\"{}\" must be a {1}x{1} array", name, size)),
+            };
+            for (bit_out, v) in row.iter().enumerate() {
+                let v = match v {
+                    Json::Number(n) => *n,
+                    Json::Null => f64::NAN,
+                    _ => return Err(format!("\"{}\" must only contain numbers", name)),
+                };
+                set_matrix_entry(&mut stats, name, bit_in, bit_out, v);
+            }
+        }
+    }
+    Ok(stats)
+}
+
+/// Parses stats written by `write_stats_csv()`.  Every matrix entry must
+/// be given exactly once.
+pub fn parse_stats_csv(text: &str) -> Result<Stats, String> {
+    // The stats, and which of the entries of each matrix have been set.
+    let mut parsed: Option<(Stats, Vec<Vec<bool>>)> = None;
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line_number == 0 || line.is_empty() {
+            continue;
+        }
+        let err = |msg: String| format!("line {}: {}", line_number + 1, msg);
+        let fields: Vec<&str> = line.split(',').collect();
+        if fields.len() != 4 {
+            return Err(err(format!("expected 4 fields, found {}", fields.len())));
+        }
+        let name = fields[0];
+        let value = fields[3];
+
+        if name == "bits" {
+            if parsed.is_some() {
+                return Err(err("\"bits\" is given more than once".into()));
+            }
+            let bits = value
+                .parse()
+                .map_err(|_| err(format!("invalid bit count \"{}\"", value)))?;
+            let stats = new_stats(bits).map_err(err)?;
+            let set = MATRIX_NAMES
+                .iter()
+                .map(|name| vec![false; matrix_size(name, bits).pow(2)])
+                .collect();
+            parsed = Some((stats, set));
+        } else if let Some(matrix) = MATRIX_NAMES.iter().position(|&n| n == name) {
+            let (stats, set) = parsed
+                .as_mut()
+                .ok_or_else(|| err("\"bits\" must come before the matrices".into()))?;
+            let size = matrix_size(name, stats.bits);
+            let index = |text: &str| match text.parse::<usize>() {
+                Ok(i) if i < size => Ok(i),
+                _ => Err(err(format!("invalid bit index \"{}\"", text))),
+            };
+            let bit_in = index(fields[1])?;
+            let bit_out = index(fields[2])?;
+            let v = value
+                .parse()
+                .map_err(|_| err(format!("invalid number \"{}\"", value)))?;
+            let was_set = &mut set[matrix][bit_in * size + bit_out];
+            if *was_set {
+                return Err(err(format!(
+                    "{}[{}][{}] is given more than once",
+                    name, bit_in, bit_out
+                )));
+            }
+            *was_set = true;
+            set_matrix_entry(stats, name, bit_in, bit_out, v);
+        }
+        // Anything else is a derived value, which is recomputed instead.
+    }
+
+    let (stats, set) = parsed.ok_or_else(|| "missing \"bits\"".to_string())?;
+    for (name, set) in MATRIX_NAMES.iter().zip(set.iter()) {
+        let size = matrix_size(name, stats.bits);
+        if let Some(i) = set.iter().position(|&was_set| !was_set) {
+            return Err(format!("missing {}[{}][{}]", name, i / size, i % size));
+        }
+    }
+    Ok(stats)
+}
+
+//----------------------------------------------------------------------
+
+const MATRIX_NAMES: [&str; 3] = ["avalanche", "avalanche_avg_bias", "tree_bias"];
+
+/// The matrices of the stats, with their names and sizes.
+fn matrices(stats: &Stats) -> Vec<(&'static str, Vec<&[f64]>, usize)> {
+    vec![
+        (
+            "avalanche",
+            stats.avalanche.iter().map(|r| &r[..]).collect(),
+            stats.bits,
+        ),
+        (
+            "avalanche_avg_bias",
+            stats.avalanche_avg_bias.iter().map(|r| &r[..]).collect(),
+            stats.bits,
+        ),
+        (
+            "tree_bias",
+            stats.tree_bias.iter().map(|r| &r[..]).collect(),
+            32,
+        ),
+    ]
+}
+
+fn matrix_size(name: &str, bits: usize) -> usize {
+    if name == "tree_bias" {
+        32
+    } else {
+        bits
+    }
+}
+
+fn set_matrix_entry(stats: &mut Stats, name: &str, bit_in: usize, bit_out: usize, v: f64) {
+    match name {
+        "avalanche" => stats.avalanche[bit_in][bit_out] = v,
+        "avalanche_avg_bias" => stats.avalanche_avg_bias[bit_in][bit_out] = v,
+        _ => stats.tree_bias[bit_in][bit_out] = v,
+    }
+}
+
+fn new_stats(bits: usize) -> Result<Stats, String> {
+    if !(1..=MAX_STATS_BITS).contains(&bits) {
+        return Err(format!(
+            "the bit count must be between 1 and {}, not {}",
+            MAX_STATS_BITS, bits
+        ));
+    }
+    Ok(Stats::zero(bits))
+}
+
+fn extension(path: &Path) -> Option<&str> {
+    path.extension().and_then(|e| e.to_str())
+}
+
+fn unknown_format(path: &Path) -> String {
+    format!(
+        "\"{}\" must end in .json or .csv, to say which format to use",
+        path.display()
+    )
+}
+
+/// JSON has no NaN or infinity, so those are written as null.
+fn json_number(x: f64) -> String {
+    if x.is_finite() {
+        format!("{:?}", x)
+    } else {
+        "null".into()
+    }
+}
+
+fn json_array(values: &[f64]) -> String {
+    let values: Vec<String> = values.iter().map(|&v| json_number(v)).collect();
+    format!("[{}]", values.join(", "))
+}
+
+//----------------------------------------------------------------------
+// A minimal JSON parser, which is all the loader needs.
+
+enum Json {
+    Null,
+    Number(f64),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+    Other, // Strings and booleans, which stats never contain.
+}
+
+struct JsonParser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl JsonParser<'_> {
+    fn error(&self, msg: &str) -> String {
+        let line = self.text[..self.pos].matches('\n').count() + 1;
+        format!("line {}: {}", line, msg)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    /// Consumes `c` if it's the next non-whitespace character.
+    fn eat(&mut self, c: u8) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), String> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c as char)))
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                if !self.eat(b'}') {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.parse_string()?;
+                        self.expect(b':')?;
+                        fields.push((key, self.parse_value()?));
+                        if !self.eat(b',') {
+                            break;
+                        }
+                    }
+                    self.expect(b'}')?;
+                }
+                Ok(Json::Object(fields))
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                if !self.eat(b']') {
+                    loop {
+                        items.push(self.parse_value()?);
+                        if !self.eat(b',') {
+                            break;
+                        }
+                    }
+                    self.expect(b']')?;
+                }
+                Ok(Json::Array(items))
+            }
+            Some(b'"') => self.parse_string().map(|_| Json::Other),
+            Some(_) => {
+                let rest = &self.text[self.pos..];
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || "+-.".contains(c)))
+                    .unwrap_or(rest.len());
+                let token = &rest[..len];
+                self.pos += len;
+                match token {
+                    "null" => Ok(Json::Null),
+                    "true" | "false" => Ok(Json::Other),
+                    _ => token
+                        .parse()
+                        .map(Json::Number)
+                        .map_err(|_| self.error(&format!("invalid value \"{}\"", token))),
+                }
+            }
+            None => Err(self.error("unexpected end of file")),
+        }
+    }
+
+    /// Parses a string.  Escapes are only validated, not decoded, since
+    /// the keys this needs never contain them.
+    fn parse_string(&mut self) -> Result<String, String> {
+        if self.peek() != Some(b'"') {
+            return Err(self.error("expected a string"));
+        }
+        let start = self.pos + 1;
+        let mut escaped = false;
+        for (i, c) in self.text[start..].char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => {
+                    self.pos = start + i + 1;
+                    return Ok(self.text[start..start + i].to_string());
+                }
+                _ => {}
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exhaustive::exhaustive_stats;
+    use crate::hash_gen::exec_hash_slice_bits;
+    use crate::hashes;
+
+    fn to_json(stats: &Stats) -> String {
+        let mut json = Vec::new();
+        write_stats_json(stats, &mut json).unwrap();
+        String::from_utf8(json).unwrap()
+    }
+
+    fn to_csv(stats: &Stats) -> String {
+        let mut csv = Vec::new();
+        write_stats_csv(stats, &mut csv).unwrap();
+        String::from_utf8(csv).unwrap()
+    }
+
+    #[test]
+    fn saved_stats_keep_bits_and_score() {
+        let ops = hashes::get::<u32>("fast").unwrap().ops.unwrap();
+        let bits = 8;
+        let stats = exhaustive_stats(
+            |n, s| exec_hash_slice_bits(ops, n, s, bits),
+            bits,
+            &[1, 0x5a, 0xc3, 0xff],
+        );
+        assert_eq!(stats.bits, bits as usize);
+        let score = score_stats(&stats);
+        assert!(score.is_finite() && score < 10.0, "score {}", score);
+
+        for loaded in [
+            parse_stats_json(&to_json(&stats)).unwrap(),
+            parse_stats_csv(&to_csv(&stats)).unwrap(),
+        ]
+        .iter()
+        {
+            assert_eq!(loaded.bits, stats.bits);
+            assert_eq!(score_stats(loaded), score);
+        }
+    }
+
+    #[test]
+    fn nan_entries_round_trip() {
+        let mut stats = Stats::zero(4);
+        stats.avalanche[1][2] = f64::NAN;
+        stats.tree_bias[3][4] = f64::NAN;
+
+        let json = to_json(&stats);
+        assert!(json.contains("null"));
+        for loaded in [
+            parse_stats_json(&json).unwrap(),
+            parse_stats_csv(&to_csv(&stats)).unwrap(),
+        ]
+        .iter()
+        {
+            assert!(loaded.avalanche[1][2].is_nan());
+            assert!(loaded.tree_bias[3][4].is_nan());
+            assert_eq!(loaded.avalanche[2][1], 0.0);
+        }
+    }
+
+    #[test]
+    fn wrong_matrix_sizes_are_rejected() {
+        let stats = Stats::zero(8);
+        let json = to_json(&stats).replace("\"bits\": 8", "\"bits\": 7");
+        let err = parse_stats_json(&json).unwrap_err();
+        assert!(err.contains("7x7"), "{}", err);
+
+        let csv = to_csv(&stats).replace("bits,,,8", "bits,,,7");
+        let err = parse_stats_csv(&csv).unwrap_err();
+        assert!(err.contains("invalid bit index"), "{}", err);
+    }
+
+    #[test]
+    fn incomplete_or_repeated_csv_entries_are_rejected() {
+        let csv = to_csv(&Stats::zero(8));
+
+        let missing = csv.replace("avalanche,3,5,0.0\n", "");
+        let err = parse_stats_csv(&missing).unwrap_err();
+        assert!(err.contains("missing avalanche[3][5]"), "{}", err);
+
+        let missing_rows: String = csv
+            .lines()
+            .filter(|line| !line.starts_with("tree_bias,31,"))
+            .map(|line| line.to_string() + "\n")
+            .collect();
+        let err = parse_stats_csv(&missing_rows).unwrap_err();
+        assert!(err.contains("missing tree_bias[31][0]"), "{}", err);
+
+        let repeated = csv.clone() + "avalanche_avg_bias,1,2,0.5\n";
+        let err = parse_stats_csv(&repeated).unwrap_err();
+        assert!(err.contains("more than once"), "{}", err);
+
+        // A second "bits" would otherwise start over and drop everything
+        // before it.
+        let restarted = csv.clone() + &csv.lines().skip(1).collect::<Vec<_>>().join("\n");
+        let err = parse_stats_csv(&restarted).unwrap_err();
+        assert!(err.contains("\"bits\" is given more than once"), "{}", err);
+    }
+
+    #[test]
+    fn missing_bits_are_rejected() {
+        let json = to_json(&Stats::zero(8)).replace("  \"bits\": 8,\n", "");
+        let err = parse_stats_json(&json).unwrap_err();
+        assert!(err.contains("missing \"bits\""), "{}", err);
+
+        let err = parse_stats_csv("name,bit_in,bit_out,value\nscore,,,0.5\n").unwrap_err();
+        assert!(err.contains("missing \"bits\""), "{}", err);
+    }
+
+    #[test]
+    fn csv_rows_before_bits_are_rejected() {
+        let csv = "name,bit_in,bit_out,value\navalanche,0,0,0.5\nbits,,,8\n";
+        let err = parse_stats_csv(csv).unwrap_err();
+        assert!(err.starts_with("line 2:"), "{}", err);
+        assert!(err.contains("before"), "{}", err);
+    }
+
+    #[test]
+    fn unknown_extensions_are_rejected() {
+        for name in ["stats.txt", "stats"].iter() {
+            let err = read_stats_file(Path::new(name)).unwrap_err();
+            assert!(err.contains(".json or .csv"), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,759 @@
+//! Colour-mapped images of hash `Stats`.
+//!
+//! Each matrix of the stats is drawn as its own panel with a title, bit
+//! axes and a colour legend.  Rows are the first index of the matrix, with
+//! 0 at the bottom, and columns are the second: input and output bits for
+//! the avalanche matrices, and output and input differences for the tree
+//! bias.
+
+use std::fs::File;
+
+use crate::stats::{target_bias, Stats};
+
+/// What the colours of a stats image show.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StatsImageMode {
+    /// The values themselves, with a perceptual colour map.
+    Values,
+
+    /// How far each value is from that of a proper Owen scramble, with a
+    /// diverging colour map: blue for too low, and red for too high.  The
+    /// ideals are the ones `score_stats()` uses: 0.5 avalanche and
+    /// `target_bias()` for the output bits above each input bit, and 0.5
+    /// tree bias.  Values without an ideal are drawn gray.
+    Deviation,
+}
+
+impl StatsImageMode {
+    pub const NAMES: &'static [&'static str] = &["values", "deviation"];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            StatsImageMode::Values => "values",
+            StatsImageMode::Deviation => "deviation",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<StatsImageMode> {
+        match name {
+            "values" => Some(StatsImageMode::Values),
+            "deviation" => Some(StatsImageMode::Deviation),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StatsImageOptions {
+    pub mode: StatsImageMode,
+
+    /// Scale the colours logarithmically, down to `LOG_SCALE_MIN`, so
+    /// that the tiny biases of the high bits are visible.  Deviations are
+    /// scaled by their magnitude, keeping their sign.
+    pub log_scale: bool,
+}
+
+impl Default for StatsImageOptions {
+    fn default() -> StatsImageOptions {
+        StatsImageOptions {
+            mode: StatsImageMode::Values,
+            log_scale: false,
+        }
+    }
+}
+
+/// The smallest magnitude that log scaled images distinguish from zero.
+pub const LOG_SCALE_MIN: f64 = 1.0e-6;
+
+/// Writes the avalanche, avalanche bias and tree bias matrices side by
+/// side as a png.
+pub fn write_stats_image(stats: &Stats, options: &StatsImageOptions, file: &mut File) {
+    draw_panels(&[stats_panels(stats, options, "")]).write_png(file);
+}
+
+/// Writes the matrices of two stats, and their signed difference `b - a`,
+/// as a png with a row of panels for each.  The stats must be for the same
+/// word size.
+pub fn write_stats_diff_image(a: &Stats, b: &Stats, options: &StatsImageOptions, file: &mut File) {
+    assert_eq!(a.bits, b.bits);
+    let bits = a.bits;
+    let suffix = if options.log_scale { " (log)" } else { "" };
+    let diff = [
+        Panel::new(
+            &format!("b - a: avalanche{}", suffix),
+            BIT_AXES,
+            bits,
+            |i, o| Some(b.avalanche[i][o] - a.avalanche[i][o]),
+            true,
+            options.log_scale,
+        ),
+        Panel::new(
+            &format!("b - a: avalanche bias{}", suffix),
+            BIT_AXES,
+            bits,
+            |i, o| Some(b.avalanche_avg_bias[i][o] - a.avalanche_avg_bias[i][o]),
+            true,
+            options.log_scale,
+        ),
+        Panel::new(
+            &format!("b - a: tree bias{}", suffix),
+            TREE_AXES,
+            32,
+            |x, y| Some(b.tree_bias[x][y] - a.tree_bias[x][y]),
+            true,
+            options.log_scale,
+        ),
+    ];
+    // A and B share their colour scales, so that their colours can be
+    // compared directly.
+    let mut panels_a = stats_panels(a, options, "a: ");
+    let mut panels_b = stats_panels(b, options, "b: ");
+    for (pa, pb) in panels_a.iter_mut().zip(panels_b.iter_mut()) {
+        let scale = pa.scale.widest(pb.scale);
+        pa.scale = scale;
+        pb.scale = scale;
+    }
+    draw_panels(&[panels_a, panels_b, diff]).write_png(file);
+}
+
+const BIT_AXES: &str = "x: bit_out  y: bit_in";
+const TREE_AXES: &str = "x: input diff  y: output diff";
+
+/// The panels of the avalanche, avalanche bias and tree bias matrices,
+/// with `prefix` at the start of their titles.
+fn stats_panels(stats: &Stats, options: &StatsImageOptions, prefix: &str) -> [Panel; 3] {
+    let bits = stats.bits;
+    let ideal = options.mode == StatsImageMode::Deviation;
+    let suffix = if options.log_scale { " (log)" } else { "" };
+    let title = |name: &str, ideal_value: &str| {
+        let deviation = if ideal {
+            format!(" - {}", ideal_value)
+        } else {
+            String::new()
+        };
+        format!("{}{}{}{}", prefix, name, deviation, suffix)
+    };
+
+    [
+        Panel::new(
+            &title("avalanche", "0.5"),
+            BIT_AXES,
+            bits,
+            |bit_in, bit_out| {
+                let v = stats.avalanche[bit_in][bit_out];
+                match options.mode {
+                    StatsImageMode::Values => Some(v),
+                    _ if bit_in < bit_out => Some(v - 0.5),
+                    _ => None,
+                }
+            },
+            ideal,
+            options.log_scale,
+        ),
+        Panel::new(
+            &title("avalanche bias", "target"),
+            BIT_AXES,
+            bits,
+            |bit_in, bit_out| {
+                let v = stats.avalanche_avg_bias[bit_in][bit_out];
+                match options.mode {
+                    StatsImageMode::Values => Some(v),
+                    _ if bit_in < bit_out => Some(v - target_bias(bit_out)),
+                    _ => None,
+                }
+            },
+            ideal,
+            options.log_scale,
+        ),
+        Panel::new(
+            &title("tree bias", "0.5"),
+            TREE_AXES,
+            32,
+            |x, y| {
+                let v = stats.tree_bias[x][y];
+                match options.mode {
+                    StatsImageMode::Values => Some(v),
+                    _ => Some(v - 0.5),
+                }
+            },
+            ideal,
+            options.log_scale,
+        ),
+    ]
+}
+
+//----------------------------------------------------------------------
+// Panels.
+
+/// Panels are at least this many pixels wide, so their labels fit.
+const MIN_PANEL_SIZE: usize = 256;
+const MIN_CELL_SIZE: usize = 8;
+const MARGIN: usize = 12;
+const TICK_WIDTH: usize = 24; // Room for two digit tick labels.
+const LEGEND_HEIGHT: usize = 10;
+const MISSING_COLOR: [u8; 3] = [0x80, 0x80, 0x80];
+const TEXT_COLOR: [u8; 3] = [0x00, 0x00, 0x00];
+
+/// A colour-mapped square matrix with its annotations.
+struct Panel {
+    title: String,
+    axes: String,
+    size: usize,
+    values: Vec<Option<f64>>, // Row-major.
+    scale: Scale,
+}
+
+impl Panel {
+    /// Makes a panel of `value(row, column)`, where `None` means there's
+    /// nothing to show.  Signed values, such as deviations and differences,
+    /// get a diverging colour scale that fits them, and the rest are
+    /// expected to be between 0 and 1.
+    fn new<F>(
+        title: &str,
+        axes: &str,
+        size: usize,
+        value: F,
+        signed: bool,
+        log_scale: bool,
+    ) -> Panel
+    where
+        F: Fn(usize, usize) -> Option<f64>,
+    {
+        let values: Vec<Option<f64>> = (0..size)
+            .flat_map(|row| (0..size).map(move |column| (row, column)))
+            .map(|(row, column)| value(row, column).filter(|v| !v.is_nan()))
+            .collect();
+        let max_magnitude = values
+            .iter()
+            .flatten()
+            .fold(0.0f64, |max, v| max.max(v.abs()));
+
+        let scale = match (signed, log_scale) {
+            (false, false) => Scale::Linear { max: 1.0 },
+            (false, true) => Scale::Log {
+                min: LOG_SCALE_MIN,
+                max: 1.0,
+            },
+            (true, false) => Scale::Signed {
+                range: nice_ceil(max_magnitude.max(LOG_SCALE_MIN)),
+            },
+            (true, true) => Scale::SignedLog {
+                min: LOG_SCALE_MIN,
+                range: decade_ceil(max_magnitude.max(LOG_SCALE_MIN * 10.0)),
+            },
+        };
+
+        Panel {
+            title: title.to_string(),
+            axes: axes.to_string(),
+            size,
+            values,
+            scale,
+        }
+    }
+
+    fn cell_size(&self) -> usize {
+        (MIN_PANEL_SIZE / self.size).max(MIN_CELL_SIZE)
+    }
+
+    fn width(&self) -> usize {
+        TICK_WIDTH + self.size * self.cell_size() + MARGIN
+    }
+
+    fn height(&self) -> usize {
+        let line = TEXT_HEIGHT + 6;
+        line + self.size * self.cell_size() + 4 + line * 2 + 4 + LEGEND_HEIGHT + 4 + TEXT_HEIGHT
+    }
+
+    /// Draws the panel with its top-left corner at `(x, y)`.
+    fn draw(&self, canvas: &mut Canvas, x: usize, y: usize) {
+        let cell = self.cell_size();
+        let matrix_size = self.size * cell;
+        let left = x + TICK_WIDTH;
+        let top = y + TEXT_HEIGHT + 6;
+        let bottom = top + matrix_size;
+
+        canvas.text(left, y, &self.title, TEXT_COLOR);
+
+        // The matrix, with row 0 at the bottom.
+        for row in 0..self.size {
+            for column in 0..self.size {
+                let color = match self.values[row * self.size + column] {
+                    Some(v) => self.scale.color(v),
+                    None => MISSING_COLOR,
+                };
+                let cell_y = bottom - (row + 1) * cell;
+                canvas.fill_rect(left + column * cell, cell_y, cell, cell, color);
+            }
+        }
+
+        // Axes.
+        let step = (self.size / 4).max(1);
+        for i in (0..self.size).step_by(step) {
+            let label = i.to_string();
+            let center = left + i * cell + cell / 2;
+            canvas.text(
+                center - text_width(&label) / 2,
+                bottom + 4,
+                &label,
+                TEXT_COLOR,
+            );
+            let row_center = bottom - i * cell - cell / 2;
+            canvas.text(
+                left - 4 - text_width(&label),
+                row_center - TEXT_HEIGHT / 2,
+                &label,
+                TEXT_COLOR,
+            );
+        }
+        let axes_y = bottom + 4 + TEXT_HEIGHT + 6;
+        canvas.text(left, axes_y, &self.axes, TEXT_COLOR);
+
+        // Legend.
+        let legend_y = axes_y + TEXT_HEIGHT + 10;
+        for i in 0..matrix_size {
+            let t = i as f64 / (matrix_size - 1) as f64;
+            canvas.fill_rect(left + i, legend_y, 1, LEGEND_HEIGHT, self.scale.color_at(t));
+        }
+        for t in self.scale.decades() {
+            let tick_x = left + (t * (matrix_size - 1) as f64).round() as usize;
+            canvas.fill_rect(tick_x, legend_y + LEGEND_HEIGHT, 1, 3, TEXT_COLOR);
+        }
+        let [low, middle, high] = self.scale.labels();
+        let labels_y = legend_y + LEGEND_HEIGHT + 4;
+        canvas.text(left, labels_y, &low, TEXT_COLOR);
+        canvas.text(
+            left + matrix_size / 2 - text_width(&middle) / 2,
+            labels_y,
+            &middle,
+            TEXT_COLOR,
+        );
+        canvas.text(
+            left + matrix_size - text_width(&high),
+            labels_y,
+            &high,
+            TEXT_COLOR,
+        );
+    }
+}
+
+/// Lays out rows of panels, with the panels of each row side by side.
+fn draw_panels(rows: &[[Panel; 3]]) -> Canvas {
+    let row_width = |row: &[Panel; 3]| row.iter().map(|p| p.width()).sum::<usize>();
+    let row_height = |row: &[Panel; 3]| row.iter().map(|p| p.height()).max().unwrap_or(0);
+    let width = MARGIN + rows.iter().map(row_width).max().unwrap_or(0);
+    let height = MARGIN
+        + rows
+            .iter()
+            .map(|row| row_height(row) + MARGIN)
+            .sum::<usize>();
+    let mut canvas = Canvas::new(width, height);
+    let mut y = MARGIN;
+    for row in rows.iter() {
+        let mut x = MARGIN;
+        for panel in row.iter() {
+            panel.draw(&mut canvas, x, y);
+            x += panel.width();
+        }
+        y += row_height(row) + MARGIN;
+    }
+    canvas
+}
+
+//----------------------------------------------------------------------
+// Colour scales.
+
+#[derive(Debug, Copy, Clone)]
+enum Scale {
+    /// From 0 to `max`.
+    Linear { max: f64 },
+
+    /// From `min` to `max`, logarithmically.
+    Log { min: f64, max: f64 },
+
+    /// From `-range` to `range`.
+    Signed { range: f64 },
+
+    /// From `-range` to `range`, logarithmically in magnitude down to
+    /// `min`, below which everything is treated as zero.
+    SignedLog { min: f64, range: f64 },
+}
+
+impl Scale {
+    fn color(&self, v: f64) -> [u8; 3] {
+        let t = match *self {
+            Scale::Linear { max } => v / max,
+            Scale::Log { min, max } => (v.max(min) / min).log10() / (max / min).log10(),
+            Scale::Signed { range } => 0.5 + 0.5 * v / range,
+            Scale::SignedLog { min, range } => {
+                let magnitude = (v.abs().max(min) / min).log10() / (range / min).log10();
+                0.5 + 0.5 * magnitude.copysign(v)
+            }
+        };
+        self.color_at(t.clamp(0.0, 1.0))
+    }
+
+    /// Whichever of two scales of the same kind covers more.
+    fn widest(self, other: Scale) -> Scale {
+        match (self, other) {
+            (Scale::Signed { range: a }, Scale::Signed { range: b }) => {
+                Scale::Signed { range: a.max(b) }
+            }
+            (Scale::SignedLog { min, range: a }, Scale::SignedLog { range: b, .. }) => {
+                Scale::SignedLog {
+                    min,
+                    range: a.max(b),
+                }
+            }
+            _ => self,
+        }
+    }
+
+    /// The colour at position `t` of the colour map, from 0 to 1.
+    fn color_at(&self, t: f64) -> [u8; 3] {
+        match *self {
+            Scale::Linear { .. } | Scale::Log { .. } => interpolate_colors(&VIRIDIS, t),
+            Scale::Signed { .. } | Scale::SignedLog { .. } => interpolate_colors(&DIVERGING, t),
+        }
+    }
+
+    /// The positions of the powers of ten in the colour map, for log
+    /// scales.
+    fn decades(&self) -> Vec<f64> {
+        let count = |min: f64, max: f64| (max / min).log10().round() as usize;
+        match *self {
+            Scale::Log { min, max } => {
+                let n = count(min, max);
+                (0..=n).map(|k| k as f64 / n as f64).collect()
+            }
+            Scale::SignedLog { min, range } => {
+                let n = count(min, range);
+                (0..=n)
+                    .flat_map(|k| {
+                        let t = 0.5 * k as f64 / n as f64;
+                        vec![0.5 - t, 0.5 + t]
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// The values at the start, middle and end of the colour map.
+    fn labels(&self) -> [String; 3] {
+        let (low, middle, high) = match *self {
+            Scale::Linear { max } => (0.0, max / 2.0, max),
+            Scale::Log { min, max } => (min, (min * max).sqrt(), max),
+            Scale::Signed { range } | Scale::SignedLog { range, .. } => (-range, 0.0, range),
+        };
+        [format_label(low), format_label(middle), format_label(high)]
+    }
+}
+
+/// Matplotlib's viridis, sampled at even intervals.
+const VIRIDIS: [[u8; 3]; 9] = [
+    [68, 1, 84],
+    [71, 44, 122],
+    [59, 81, 139],
+    [44, 113, 142],
+    [33, 144, 141],
+    [39, 173, 129],
+    [92, 200, 99],
+    [170, 220, 50],
+    [253, 231, 37],
+];
+
+/// ColorBrewer's RdBu, from blue to red, sampled at even intervals.
+const DIVERGING: [[u8; 3]; 11] = [
+    [5, 48, 97],
+    [33, 102, 172],
+    [67, 147, 195],
+    [146, 197, 222],
+    [209, 229, 240],
+    [247, 247, 247],
+    [253, 219, 199],
+    [244, 165, 130],
+    [214, 96, 77],
+    [178, 24, 43],
+    [103, 0, 31],
+];
+
+fn interpolate_colors(colors: &[[u8; 3]], t: f64) -> [u8; 3] {
+    let x = t * (colors.len() - 1) as f64;
+    let i = (x as usize).min(colors.len() - 2);
+    let f = x - i as f64;
+    let mut color = [0u8; 3];
+    for c in 0..3 {
+        let a = colors[i][c] as f64;
+        let b = colors[i + 1][c] as f64;
+        color[c] = (a + (b - a) * f).round() as u8;
+    }
+    color
+}
+
+/// The smallest 1, 2 or 5 times a power of ten that's at least `x`.
+fn nice_ceil(x: f64) -> f64 {
+    let decade = 10.0f64.powf(x.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|m| m * decade)
+        .find(|&v| v >= x)
+        .unwrap()
+}
+
+/// The smallest power of ten that's at least `x`.
+fn decade_ceil(x: f64) -> f64 {
+    10.0f64.powf(x.log10().ceil())
+}
+
+fn format_label(x: f64) -> String {
+    if x == 0.0 {
+        "0".to_string()
+    } else if x.abs() >= 0.01 && x.abs() < 1000.0 {
+        format!("{}", (x * 1.0e4).round() / 1.0e4)
+    } else {
+        format!("{:.0e}", x)
+    }
+}
+
+//----------------------------------------------------------------------
+// Drawing.
+
+/// An RGB image, with rows stored top-down.
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<[u8; 3]>,
+}
+
+impl Canvas {
+    /// A white canvas.
+    fn new(width: usize, height: usize) -> Canvas {
+        Canvas {
+            width,
+            height,
+            pixels: vec![[0xff; 3]; width * height],
+        }
+    }
+
+    /// Fills a rectangle, clipped to the canvas.
+    fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: [u8; 3]) {
+        for yy in y..(y + height).min(self.height) {
+            for xx in x..(x + width).min(self.width) {
+                self.pixels[yy * self.width + xx] = color;
+            }
+        }
+    }
+
+    /// Draws text with its top-left corner at `(x, y)`.  Letters are drawn
+    /// as capitals, and characters without a glyph are left blank.
+    fn text(&mut self, x: usize, y: usize, text: &str, color: [u8; 3]) {
+        for (i, c) in text.chars().enumerate() {
+            let glyph = glyph(c);
+            let glyph_x = x + i * GLYPH_ADVANCE;
+            for (row, bits) in glyph.iter().enumerate() {
+                for column in 0..3 {
+                    if bits & (0b100 >> column) != 0 {
+                        self.fill_rect(
+                            glyph_x + column * TEXT_SCALE,
+                            y + row * TEXT_SCALE,
+                            TEXT_SCALE,
+                            TEXT_SCALE,
+                            color,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn write_png(&self, file: &mut File) {
+        // Note that png rows are stored bottom-up.
+        let mut image = Vec::with_capacity(self.width * self.height * 4);
+        for row in self.pixels.chunks_exact(self.width).rev() {
+            for pixel in row.iter() {
+                image.extend_from_slice(&[pixel[0], pixel[1], pixel[2], 0xff]);
+            }
+        }
+        png_encode_mini::write_rgba_from_u8(file, &image, self.width as u32, self.height as u32)
+            .unwrap();
+    }
+}
+
+/// Glyphs are 3x5 pixels, drawn at this scale.
+const TEXT_SCALE: usize = 2;
+const TEXT_HEIGHT: usize = 5 * TEXT_SCALE;
+const GLYPH_ADVANCE: usize = 4 * TEXT_SCALE;
+
+fn text_width(text: &str) -> usize {
+    (text.chars().count() * GLYPH_ADVANCE).saturating_sub(TEXT_SCALE)
+}
+
+/// The rows of a character's glyph, top to bottom, with the leftmost
+/// pixel in the highest of the three bits.
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_lowercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'a' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'b' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'c' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'd' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'e' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'f' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'g' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'h' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'i' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'j' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'k' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'l' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'm' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'n' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'o' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'p' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'r' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        's' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        't' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'u' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'v' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'w' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'x' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+        ')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+        '<' => [0b001, 0b010, 0b100, 0b010, 0b001],
+        '>' => [0b100, 0b010, 0b001, 0b010, 0b100],
+        _ => [0; 5],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() <= 1.0e-12 * b.abs(), "{} != {}", a, b);
+    }
+
+    #[test]
+    fn signed_scales_are_centered_on_zero() {
+        let last = DIVERGING.len() - 1;
+        for &(scale, range) in [
+            (Scale::Signed { range: 0.25 }, 0.25),
+            (
+                Scale::SignedLog {
+                    min: LOG_SCALE_MIN,
+                    range: 0.01,
+                },
+                0.01,
+            ),
+        ]
+        .iter()
+        {
+            assert_eq!(scale.color(0.0), DIVERGING[last / 2], "{:?}", scale);
+            assert_eq!(scale.color(-range), DIVERGING[0], "{:?}", scale);
+            assert_eq!(scale.color(range), DIVERGING[last], "{:?}", scale);
+            assert_eq!(scale.color(-2.0 * range), DIVERGING[0], "{:?}", scale);
+            assert_eq!(scale.color(2.0 * range), DIVERGING[last], "{:?}", scale);
+        }
+
+        // Magnitudes below the minimum are zero on a log scale.
+        let scale = Scale::SignedLog {
+            min: LOG_SCALE_MIN,
+            range: 0.01,
+        };
+        assert_eq!(scale.color(LOG_SCALE_MIN / 10.0), DIVERGING[last / 2]);
+        assert_eq!(scale.color(-LOG_SCALE_MIN / 10.0), DIVERGING[last / 2]);
+    }
+
+    #[test]
+    fn log_scales_start_at_their_minimum() {
+        let last = VIRIDIS.len() - 1;
+        let scale = Scale::Log {
+            min: LOG_SCALE_MIN,
+            max: 1.0,
+        };
+        assert_eq!(scale.color(0.0), VIRIDIS[0]);
+        assert_eq!(scale.color(LOG_SCALE_MIN), VIRIDIS[0]);
+        assert_eq!(scale.color(1.0), VIRIDIS[last]);
+        assert_eq!(scale.color(1.0e-3), VIRIDIS[last / 2]);
+    }
+
+    #[test]
+    fn nice_and_decade_ceilings() {
+        for &(x, nice, decade) in [
+            (1.0, 1.0, 1.0),
+            (1.5, 2.0, 10.0),
+            (3.0, 5.0, 10.0),
+            (7.0, 10.0, 10.0),
+            (0.02, 0.02, 0.1),
+            (0.0031, 0.005, 0.01),
+            (1.0e-6, 1.0e-6, 1.0e-6),
+        ]
+        .iter()
+        {
+            assert_close(nice_ceil(x), nice);
+            assert_close(decade_ceil(x), decade);
+        }
+    }
+
+    #[test]
+    fn deviations_are_from_the_ideal_above_each_input_bit() {
+        let bits = 8;
+        let mut stats = Stats::zero(bits);
+        for bit_in in 0..bits {
+            for bit_out in 0..bits {
+                stats.avalanche[bit_in][bit_out] = 0.25;
+                stats.avalanche_avg_bias[bit_in][bit_out] = 0.125;
+            }
+        }
+        for row in stats.tree_bias.iter_mut() {
+            for v in row.iter_mut() {
+                *v = 0.75;
+            }
+        }
+
+        let options = StatsImageOptions {
+            mode: StatsImageMode::Deviation,
+            log_scale: false,
+        };
+        let [avalanche, bias, tree] = stats_panels(&stats, &options, "");
+        for bit_in in 0..bits {
+            for bit_out in 0..bits {
+                let i = bit_in * bits + bit_out;
+                if bit_in >= bit_out {
+                    assert_eq!(avalanche.values[i], None);
+                    assert_eq!(bias.values[i], None);
+                } else {
+                    assert_eq!(avalanche.values[i], Some(0.25 - 0.5));
+                    assert_eq!(bias.values[i], Some(0.125 - target_bias(bit_out)));
+                }
+            }
+        }
+        assert!(tree.values.iter().all(|&v| v == Some(0.75 - 0.5)));
+
+        // The plain values have no gaps.
+        let [avalanche, bias, tree] = stats_panels(&stats, &StatsImageOptions::default(), "");
+        assert!(avalanche.values.iter().all(|&v| v == Some(0.25)));
+        assert!(bias.values.iter().all(|&v| v == Some(0.125)));
+        assert!(tree.values.iter().all(|&v| v == Some(0.75)));
+    }
+}
@@ -0,0 +1,105 @@
+//! Abstraction over the word size that hashes operate on.
+//!
+//! Most of the hash machinery is generic over `Word`, which is
+//! implemented for `u32` and `u64`.
+
+use std::fmt::{Debug, Display, LowerHex};
+use std::hash::Hasher;
+use std::ops::{BitAnd, BitOr, BitXor, Not, Shl, Shr};
+
+use rand::Rng;
+
+pub trait Word:
+    Copy
+    + Default
+    + Eq
+    + Debug
+    + Display
+    + LowerHex
+    + Send
+    + Sync
+    + 'static
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + BitXor<Output = Self>
+    + Not<Output = Self>
+    + Shl<u32, Output = Self>
+    + Shr<u32, Output = Self>
+{
+    const BITS: u32;
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn wrapping_add(self, other: Self) -> Self;
+    fn wrapping_mul(self, other: Self) -> Self;
+    fn reverse_bits(self) -> Self;
+
+    /// Truncating conversion from a `u64`.
+    fn from_u64(n: u64) -> Self;
+    fn to_u64(self) -> u64;
+
+    fn from_str_radix(text: &str, radix: u32) -> Result<Self, std::num::ParseIntError>;
+
+    /// A uniformly distributed random word.
+    fn random<R: Rng + ?Sized>(rng: &mut R) -> Self;
+
+    /// Feeds the word to a `Hasher`, as a value of its own size.
+    fn write_to_hasher<H: Hasher>(self, hasher: &mut H);
+
+    /// Whether bit `bit` is set.
+    #[inline]
+    fn bit(self, bit: u32) -> bool {
+        (self >> bit) & Self::ONE == Self::ONE
+    }
+}
+
+macro_rules! impl_word {
+    ($t:ty, $write:ident) => {
+        impl Word for $t {
+            const BITS: u32 = <$t>::BITS;
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+
+            #[inline(always)]
+            fn wrapping_add(self, other: Self) -> Self {
+                <$t>::wrapping_add(self, other)
+            }
+
+            #[inline(always)]
+            fn wrapping_mul(self, other: Self) -> Self {
+                <$t>::wrapping_mul(self, other)
+            }
+
+            #[inline(always)]
+            fn reverse_bits(self) -> Self {
+                <$t>::reverse_bits(self)
+            }
+
+            #[inline(always)]
+            fn from_u64(n: u64) -> Self {
+                n as $t
+            }
+
+            #[inline(always)]
+            fn to_u64(self) -> u64 {
+                self as u64
+            }
+
+            fn from_str_radix(text: &str, radix: u32) -> Result<Self, std::num::ParseIntError> {
+                <$t>::from_str_radix(text, radix)
+            }
+
+            #[inline(always)]
+            fn random<R: Rng + ?Sized>(rng: &mut R) -> Self {
+                rng.gen::<$t>()
+            }
+
+            fn write_to_hasher<H: Hasher>(self, hasher: &mut H) {
+                hasher.$write(self);
+            }
+        }
+    };
+}
+
+impl_word!(u32, write_u32);
+impl_word!(u64, write_u64);